@@ -0,0 +1,349 @@
+use std::collections::HashMap;
+use std::process::{Command, ExitStatus, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, bail, Context, Result};
+use serde::Deserialize;
+
+use crate::stats::{ActiveData, UsageWindow};
+
+/// Default re-arm margin for `utilization` rules, in percentage points.
+pub const DEFAULT_HYSTERESIS: f64 = 5.0;
+
+/// How long a command, webhook or notification may take before it's given up.
+pub const ACTION_TIMEOUT: Duration = Duration::from_secs(10);
+
+const LEVELS: [&str; 4] = ["normal", "warn", "danger", "over"];
+
+/// One `[[alert]]` table. Every condition that is set must hold for the rule
+/// to fire; it fires once and re-arms only after the condition clears.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AlertRule {
+    /// Notification title; defaults to "Claude Code usage".
+    pub name: Option<String>,
+    /// `session` or `weekly`; every window, per-model ones included, when
+    /// unset.
+    pub window: Option<String>,
+    /// Fire when utilization reaches this percentage.
+    pub utilization: Option<f64>,
+    /// Fire when `usage_level` is at least this (`warn`, `danger`, `over`).
+    pub level: Option<String>,
+    /// Fire when the pace says usage runs out before the window resets.
+    pub runs_out: Option<bool>,
+    /// Fire when usage is projected to run out within this many minutes.
+    pub eta_below: Option<f64>,
+    /// How far utilization must drop below `utilization` before the rule
+    /// can fire again.
+    pub hysteresis: Option<f64>,
+    /// Shell command to run; the alert is passed in `ALERT_*` variables.
+    pub command: Option<String>,
+    /// URL that receives the alert as a JSON POST.
+    pub webhook: Option<String>,
+    /// Show a desktop notification.
+    pub desktop: Option<bool>,
+}
+
+/// Maps a window name to the label used on screen.
+fn window_label(name: &str) -> Option<&'static str> {
+    match name {
+        "session" | "five_hour" => Some("Session"),
+        "weekly" | "seven_day" => Some("Weekly"),
+        _ => None,
+    }
+}
+
+fn level_rank(level: &str) -> Option<usize> {
+    LEVELS.iter().position(|l| *l == level)
+}
+
+fn format_minutes(minutes: f64) -> String {
+    let total = minutes.max(0.0).round() as u64;
+    match (total / 1440, total % 1440 / 60, total % 60) {
+        (0, 0, m) => format!("{m}m"),
+        (0, h, 0) => format!("{h}h"),
+        (0, h, m) => format!("{h}h {m}m"),
+        (d, 0, _) => format!("{d}d"),
+        (d, h, _) => format!("{d}d {h}h"),
+    }
+}
+
+impl AlertRule {
+    fn has_condition(&self) -> bool {
+        self.utilization.is_some()
+            || self.level.is_some()
+            || self.runs_out.is_some()
+            || self.eta_below.is_some()
+    }
+
+    fn has_action(&self) -> bool {
+        self.command.is_some() || self.webhook.is_some() || self.desktop == Some(true)
+    }
+
+    fn applies_to(&self, label: &str) -> bool {
+        self.window
+            .as_deref()
+            .is_none_or(|w| window_label(w) == Some(label))
+    }
+
+    fn matches(&self, w: &UsageWindow) -> bool {
+        let pace = w.pace.as_ref();
+        self.utilization.is_none_or(|t| w.utilization >= t)
+            && self
+                .level
+                .as_deref()
+                .is_none_or(|l| level_rank(&w.usage_level) >= level_rank(l))
+            && self
+                .runs_out
+                .is_none_or(|r| pace.is_some_and(|p| !p.will_last_to_reset) == r)
+            && self.eta_below.is_none_or(|limit| {
+                pace.and_then(|p| p.eta_minutes)
+                    .is_some_and(|eta| eta < limit)
+            })
+    }
+
+    /// True once the condition no longer holds, with the hysteresis margin
+    /// applied to `utilization` so readings hovering at the threshold
+    /// don't fire repeatedly.
+    fn cleared(&self, w: &UsageWindow) -> bool {
+        if let Some(threshold) = self.utilization {
+            let margin = self.hysteresis.unwrap_or(DEFAULT_HYSTERESIS);
+            if w.utilization < threshold - margin {
+                return true;
+            }
+            if w.utilization >= threshold {
+                return false;
+            }
+        }
+        let relaxed = AlertRule {
+            utilization: None,
+            ..self.clone()
+        };
+        relaxed.has_condition() && !relaxed.matches(w)
+    }
+
+    fn describe(&self, label: &str, w: &UsageWindow) -> String {
+        let pct = w.utilization.round();
+        if let Some(eta) = w
+            .pace
+            .as_ref()
+            .filter(|_| self.runs_out.is_some() || self.eta_below.is_some())
+            .and_then(|p| p.eta_minutes)
+        {
+            return format!(
+                "{label} usage at {pct}%, runs out in {}",
+                format_minutes(eta)
+            );
+        }
+        if self.level.is_some() {
+            return format!("{label} usage at {pct}% ({})", w.usage_level);
+        }
+        format!("{label} usage at {pct}%")
+    }
+}
+
+/// Checks the rules from the config.
+pub fn validate(rules: &[AlertRule]) -> Result<()> {
+    for (i, rule) in rules.iter().enumerate() {
+        let name = rule.name.clone().unwrap_or_else(|| format!("#{}", i + 1));
+        if !rule.has_condition() {
+            bail!("alert {name}: set at least one of utilization, level, runs_out or eta_below");
+        }
+        if !rule.has_action() {
+            bail!("alert {name}: set at least one of command, webhook or desktop = true");
+        }
+        if let Some(w) = rule.window.as_deref().filter(|w| window_label(w).is_none()) {
+            bail!("alert {name}: unknown window `{w}` (expected session or weekly)");
+        }
+        if let Some(l) = rule.level.as_deref().filter(|l| level_rank(l).is_none()) {
+            bail!("alert {name}: unknown level `{l}` (expected warn, danger or over)");
+        }
+        if rule.hysteresis.is_some_and(|h| h < 0.0) {
+            bail!("alert {name}: hysteresis must not be negative");
+        }
+    }
+    Ok(())
+}
+
+/// A rule that fired for one usage window.
+#[derive(Debug, Clone)]
+pub struct Alert {
+    pub rule: AlertRule,
+    /// The window's label: `Session`, `Weekly` or e.g. `Weekly Opus`.
+    pub window: String,
+    pub message: String,
+    pub utilization: f64,
+    pub usage_level: String,
+    pub eta_minutes: Option<f64>,
+}
+
+impl Alert {
+    pub fn title(&self) -> String {
+        self.rule
+            .name
+            .clone()
+            .unwrap_or_else(|| "Claude Code usage".to_string())
+    }
+}
+
+/// Evaluates alert rules across updates, remembering which ones have fired
+/// so each crossing is reported once.
+#[derive(Debug, Default)]
+pub struct Alerter {
+    rules: Vec<AlertRule>,
+    /// Rule index and window label of alerts waiting to re-arm.
+    fired: HashMap<(usize, String), bool>,
+}
+
+impl Alerter {
+    pub fn new(rules: Vec<AlertRule>) -> Self {
+        Alerter {
+            rules,
+            fired: HashMap::new(),
+        }
+    }
+
+    pub fn rules(&self) -> &[AlertRule] {
+        &self.rules
+    }
+
+    /// Returns the alerts that newly fired for `data`.
+    pub fn check(&mut self, data: &ActiveData) -> Vec<Alert> {
+        let windows = data.windows();
+        let mut alerts = Vec::new();
+        for (i, rule) in self.rules.iter().enumerate() {
+            for &(label, w) in &windows {
+                if !rule.applies_to(label) {
+                    continue;
+                }
+                let fired = self.fired.entry((i, label.to_string())).or_default();
+                if *fired {
+                    if rule.cleared(w) {
+                        *fired = false;
+                    }
+                } else if rule.matches(w) {
+                    *fired = true;
+                    alerts.push(Alert {
+                        rule: rule.clone(),
+                        window: label.to_string(),
+                        message: rule.describe(label, w),
+                        utilization: w.utilization,
+                        usage_level: w.usage_level.clone(),
+                        eta_minutes: w.pace.as_ref().and_then(|p| p.eta_minutes),
+                    });
+                }
+            }
+        }
+        alerts
+    }
+}
+
+/// Runs every action configured for the alert's rule. All actions are
+/// attempted; the first failure is returned.
+pub fn dispatch(alert: &Alert) -> Result<()> {
+    let mut first_error = None;
+    let mut record = |result: Result<()>| {
+        if let Err(e) = result {
+            first_error.get_or_insert(e);
+        }
+    };
+    if let Some(command) = &alert.rule.command {
+        record(run_command(command, alert));
+    }
+    if let Some(url) = &alert.rule.webhook {
+        record(post_webhook(url, alert));
+    }
+    if alert.rule.desktop == Some(true) {
+        record(notify_desktop(alert));
+    }
+    first_error.map_or(Ok(()), Err)
+}
+
+fn run_command(command: &str, alert: &Alert) -> Result<()> {
+    let mut cmd = Command::new("sh");
+    cmd.args(["-c", command])
+        .env("ALERT_NAME", alert.title())
+        .env("ALERT_WINDOW", &alert.window)
+        .env("ALERT_MESSAGE", &alert.message)
+        .env("ALERT_UTILIZATION", format!("{:.1}", alert.utilization))
+        .env("ALERT_LEVEL", &alert.usage_level)
+        .env(
+            "ALERT_ETA_MINUTES",
+            alert
+                .eta_minutes
+                .map(|m| format!("{m:.0}"))
+                .unwrap_or_default(),
+        )
+        .stdin(Stdio::null());
+    let status =
+        run_with_timeout(&mut cmd).with_context(|| format!("alert command `{command}`"))?;
+    if !status.success() {
+        bail!("alert command `{command}` exited with {status}");
+    }
+    Ok(())
+}
+
+/// Runs `cmd` to completion, killing it after `ACTION_TIMEOUT` so a hung
+/// hook can't hold up the update.
+fn run_with_timeout(cmd: &mut Command) -> Result<ExitStatus> {
+    let mut child = cmd.spawn().context("failed to start")?;
+    let deadline = Instant::now() + ACTION_TIMEOUT;
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(status);
+        }
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            bail!("timed out after {}s", ACTION_TIMEOUT.as_secs());
+        }
+        thread::sleep(Duration::from_millis(20));
+    }
+}
+
+fn post_webhook(url: &str, alert: &Alert) -> Result<()> {
+    let body = serde_json::json!({
+        "name": alert.title(),
+        "window": alert.window,
+        "message": alert.message,
+        "utilization": alert.utilization,
+        "usage_level": alert.usage_level,
+        "eta_minutes": alert.eta_minutes,
+    });
+    reqwest::blocking::Client::builder()
+        .timeout(ACTION_TIMEOUT)
+        .build()?
+        .post(url)
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .body(body.to_string())
+        .send()
+        .and_then(|resp| resp.error_for_status())
+        .with_context(|| format!("webhook {url} failed"))?;
+    Ok(())
+}
+
+fn notify_desktop(alert: &Alert) -> Result<()> {
+    let title = alert.title();
+    let mut cmd = if cfg!(target_os = "macos") {
+        let quote = |s: &str| s.replace('\\', "\\\\").replace('"', "\\\"");
+        let script = format!(
+            "display notification \"{}\" with title \"{}\"",
+            quote(&alert.message),
+            quote(&title)
+        );
+        let mut cmd = Command::new("osascript");
+        cmd.args(["-e", &script]);
+        cmd
+    } else {
+        let mut cmd = Command::new("notify-send");
+        cmd.args([title.as_str(), alert.message.as_str()]);
+        cmd
+    };
+    let status = run_with_timeout(&mut cmd)
+        .map_err(|e| anyhow!("failed to show desktop notification: {e:#}"))?;
+    if !status.success() {
+        bail!("desktop notification exited with {status}");
+    }
+    Ok(())
+}
@@ -0,0 +1,72 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+
+use crate::schedule::WorkingHours;
+use crate::stats::{ActiveData, UsageWindow};
+
+/// What an allowance is spread over.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Period {
+    Hour,
+    Day,
+    /// A day with working time left in it, per the `working_hours` and
+    /// `working_days` settings.
+    WorkingDay,
+}
+
+impl Period {
+    /// Label for `count` periods, e.g. "3 work days".
+    pub fn describe(self, count: u32) -> String {
+        let unit = match self {
+            Period::Hour => "hour",
+            Period::Day => "day",
+            Period::WorkingDay => "work day",
+        };
+        let plural = if count == 1 { "" } else { "s" };
+        format!("{count} {unit}{plural}")
+    }
+}
+
+/// How much of a window can be used per period and still last to its reset.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Allowance {
+    /// Percentage points of the window per period.
+    pub percent: f64,
+    pub period: Period,
+    /// Periods left before the reset, counting the current one.
+    pub periods: u32,
+}
+
+fn allowance(window: &UsageWindow, period: Period, periods: u32) -> Allowance {
+    let periods = periods.max(1);
+    Allowance {
+        percent: (100.0 - window.utilization).max(0.0) / f64::from(periods),
+        period,
+        periods,
+    }
+}
+
+/// Fills in the allowance of each window with a reset time: per hour for
+/// the session window and others up to a day long, per day for the weekly
+/// ones. With working hours set, the daily allowance is per working day
+/// left, unless none are left before the reset.
+pub fn apply(data: &mut ActiveData, hours: Option<&WorkingHours>, now: DateTime<Utc>) {
+    for (_, length, w) in data.windows_by_key_mut() {
+        let Some(resets_in) = w.resets_in_minutes else {
+            continue;
+        };
+        if length.is_some_and(|length| length <= 1440.0) {
+            let hours_left = (resets_in / 60.0).ceil() as u32;
+            w.allowance = Some(allowance(w, Period::Hour, hours_left));
+            continue;
+        }
+        let reset = now + Duration::seconds((resets_in * 60.0) as i64);
+        let working_days = hours.map_or(0, |h| h.working_days(now, reset));
+        w.allowance = Some(if working_days > 0 {
+            allowance(w, Period::WorkingDay, working_days)
+        } else {
+            allowance(w, Period::Day, (resets_in / 1440.0).ceil() as u32)
+        });
+    }
+}
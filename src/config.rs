@@ -1,15 +1,309 @@
+use std::collections::BTreeMap;
 use std::env;
+use std::fmt;
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::time::SystemTime;
 
-use anyhow::{Context, Result};
+use anyhow::{anyhow, bail, Context, Result};
 use serde::Deserialize;
 
+use crate::alert::AlertRule;
+
 #[derive(Debug, Default, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
 pub struct AppConfig {
     pub host: Option<String>,
     pub daemon: Option<u64>,
     pub with_disk: Option<bool>,
+    /// Add the daily/hourly budget page to the album.
+    pub with_budget: Option<bool>,
+    /// Add the CPU, memory and load page to the album.
+    pub with_system: Option<bool>,
+    /// Add the network throughput page to the album.
+    pub with_network: Option<bool>,
+    /// Interfaces on the network page, e.g. `["eth0", "wlan0"]`; the two
+    /// busiest when unset.
+    pub network_interfaces: Option<Vec<String>>,
+    /// Add the temperature and fan page to the album.
+    pub with_sensors: Option<bool>,
+    /// Sensors on the sensors page, matched case-insensitively against ids
+    /// like `coretemp/Package id 0`; the hottest when unset.
+    pub sensors: Option<Vec<String>>,
+    /// °C from which temperatures show as hot, overriding the chips' own
+    /// limits.
+    pub temp_warn: Option<f64>,
+    /// °C from which temperatures show as critical.
+    pub temp_critical: Option<f64>,
+    /// Display brightness, 0-100.
+    pub brightness: Option<u8>,
+    /// Ordered-dither gradients to the panel's RGB565 palette.
+    pub dither: Option<bool>,
+    /// Minutes after which the stats screen marks its data as stale.
+    pub stale_after: Option<u64>,
+    /// Where usage stats come from; see [`crate::source::parse`].
+    pub source: Option<String>,
+    /// How run-out times are estimated: linear, recent, smoothed or weekday.
+    pub pace_model: Option<String>,
+    /// Hours when usage is expected, e.g. `09:00-18:00`.
+    pub working_hours: Option<String>,
+    /// Days when usage is expected, e.g. `["mon", "tue"]`.
+    pub working_days: Option<Vec<String>>,
+    /// IANA time zone for the working hours; the system zone when unset.
+    pub timezone: Option<String>,
+    /// In daemon mode, replace the album with a full-screen alert page while
+    /// a usage window is in `danger` or `over`.
+    pub alert_page: Option<bool>,
+    /// Address for the Prometheus metrics endpoint in daemon mode.
+    pub metrics: Option<String>,
+    /// Address for the local status API in daemon mode.
+    pub status_addr: Option<String>,
+    /// Log verbosity: error, warn, info, debug or trace.
+    pub log_level: Option<String>,
+    /// Log format: text or json.
+    pub log_format: Option<String>,
+    /// Threshold alerts, one `[[alert]]` table each.
+    #[serde(default, rename = "alert")]
+    pub alerts: Vec<AlertRule>,
+    /// Profile used when neither `--profile` nor `GEEKMAGIC_PROFILE` is set.
+    pub default_profile: Option<String>,
+    /// Named overrides, selected with `--profile`. Each profile accepts the
+    /// same keys as the top level and inherits anything it doesn't set.
+    #[serde(default, rename = "profile")]
+    pub profiles: BTreeMap<String, AppConfig>,
+}
+
+impl AppConfig {
+    /// Range checks that the types alone don't cover, shared by both
+    /// binaries.
+    pub fn validate(&self) -> Result<()> {
+        if let Some(brightness) = self.brightness.filter(|b| *b > 100) {
+            bail!("brightness must be between 0 and 100, got {brightness}");
+        }
+        Ok(())
+    }
+}
+
+/// Commented starter file written by `geekmagic-stats config init`.
+pub const STARTER_CONFIG: &str = r#"# geekmagic-stats configuration
+#
+# Command-line flags and GEEKMAGIC_* environment variables take precedence
+# over the values in this file.
+
+# IP address or hostname of the GeekMagic display. Required for uploads
+# unless you only render to a file with --output.
+# host = "192.168.1.50"
+
+# Run as a daemon, pushing every N seconds (minimum 10).
+# daemon = 300
+
+# Also render the disk usage screen and cycle both as an album.
+# with_disk = false
+
+# Also render a page with how much you can use per hour (session) and per
+# day (weekly, per working day when working hours are set) to last until
+# each window resets.
+# with_budget = false
+
+# Also render a page with CPU, memory and load, with graphs of the recent
+# readings (one per push).
+# with_system = false
+
+# Also render a page with receive/send rates per network interface, totals
+# since boot and a graph of the recent rates. Shows the two busiest
+# interfaces unless network_interfaces lists them (two per page).
+# with_network = false
+# network_interfaces = ["eth0", "wlan0"]
+
+# Also render a page with temperatures and fan speeds (Linux only). Shows
+# the five hottest sensors unless sensors lists them; each entry matches
+# the first sensor whose id (chip/label, as in the --json output) contains
+# it. Temperatures turn orange at temp_warn and red at temp_critical, which
+# default to the chip's own limits, or 80 and 95 °C.
+# with_sensors = false
+# sensors = ["Package id 0", "nvme", "fan1"]
+# temp_warn = 80
+# temp_critical = 95
+
+# Display brightness (0-100). Left unchanged when unset.
+# brightness = 80
+
+# Dither gradients to reduce banding on the 16-bit (RGB565) panel.
+# dither = false
+
+# Minutes after which the stats screen dims and shows the data's age.
+# stale_after = 30
+
+# Where usage stats come from: "claude-code-stats" (in-process, default),
+# "file:/path/payload.json", "stdin", "command:my-script" or an http(s) URL.
+# source = "claude-code-stats"
+
+# How the "Out in" estimate is projected: "linear" (average since the
+# window started, default), "recent" (last two hours), "smoothed"
+# (exponentially weighted) or "weekday" (per-weekday averages of past weeks
+# for the weekly window). All but linear record samples in
+# ~/.local/state/geekmagic-stats/history.json.
+# pace_model = "linear"
+
+# Only count working time towards the expected usage (the pace marker and
+# the reserve/deficit), so it stands still overnight and on weekends.
+# working_hours = "09:00-18:00"
+# working_days = ["mon", "tue", "wed", "thu", "fri"]
+# timezone = "Europe/Stockholm"
+
+# In daemon mode, switch the display to a full-screen alert page (with the
+# slideshow stopped) while a usage window is in danger or over its limit.
+# alert_page = true
+
+# Serve Prometheus metrics at http://ADDR/metrics while running as a daemon.
+# metrics = "127.0.0.1:9184"
+
+# Serve the daemon's local status API at ADDR, which `geekmagic-stats status`
+# queries.
+# status_addr = "127.0.0.1:9185"
+
+# Log verbosity ("error", "warn", "info", "debug", "trace") and format
+# ("text", or "json" for journald/launchd log collection). -v, -q and
+# --log-format override these.
+# log_level = "info"
+# log_format = "text"
+
+# Alerts fire once when their condition is met and re-arm after it clears.
+# Conditions: utilization (percent), level ("warn", "danger", "over"),
+# runs_out (pace says usage won't last to reset), eta_below (minutes).
+# Actions: command (ALERT_* variables describe the alert), webhook (JSON
+# POST) and desktop notifications.
+#
+# [[alert]]
+# window = "session"
+# utilization = 80
+# desktop = true
+#
+# [[alert]]
+# name = "Weekly budget"
+# window = "weekly"
+# runs_out = true
+# webhook = "https://example.com/hooks/claude"
+
+# Named profiles override the settings above; pick one with --profile.
+# default_profile = "home"
+#
+# [profile.office]
+# host = "10.0.1.102"
+# with_disk = true
+# brightness = 40
+#
+# [profile.home]
+# host = "192.168.1.50"
+"#;
+
+/// Prefix for environment variables that override config keys.
+pub const ENV_PREFIX: &str = "GEEKMAGIC_";
+
+/// Environment variable naming the config file when `--config` isn't given.
+pub const ENV_CONFIG_PATH: &str = "GEEKMAGIC_CONFIG";
+
+/// Environment variable selecting a profile when `--profile` isn't given.
+pub const ENV_PROFILE: &str = "GEEKMAGIC_PROFILE";
+
+/// Where an effective setting came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+    Cli,
+    Env,
+    Profile,
+    File,
+    Default,
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Source::Cli => "command line",
+            Source::Env => "environment",
+            Source::Profile => "profile",
+            Source::File => "config file",
+            Source::Default => "default",
+        })
+    }
+}
+
+/// Picks the highest-precedence value: CLI flag, then the loaded config
+/// (environment over profile over file), then default.
+pub fn pick<T>(cli: Option<T>, configured: Option<T>, default: Option<T>) -> Option<(T, Source)> {
+    cli.map(|v| (v, Source::Cli))
+        .or_else(|| configured.map(|v| (v, Source::File)))
+        .or_else(|| default.map(|v| (v, Source::Default)))
+}
+
+/// Merged config along with the layer each top-level key came from.
+#[derive(Debug, Clone, Default)]
+pub struct LoadedConfig {
+    pub config: AppConfig,
+    sources: BTreeMap<String, Source>,
+    origins: BTreeMap<String, PathBuf>,
+    files: Vec<PathBuf>,
+    profile: Option<String>,
+    warnings: Vec<String>,
+}
+
+impl LoadedConfig {
+    /// Name of the active profile, if any.
+    pub fn profile(&self) -> Option<&str> {
+        self.profile.as_deref()
+    }
+
+    pub fn source_of(&self, key: &str) -> Source {
+        self.sources.get(key).copied().unwrap_or(Source::Default)
+    }
+
+    /// The config file that last set `key`, if it came from a file.
+    pub fn origin(&self, key: &str) -> Option<&Path> {
+        self.origins.get(key).map(PathBuf::as_path)
+    }
+
+    /// Problems that didn't stop the config from loading, such as unknown
+    /// `GEEKMAGIC_*` variables.
+    pub fn warnings(&self) -> &[String] {
+        &self.warnings
+    }
+
+    /// Files that were read, lowest precedence first.
+    pub fn files(&self) -> &[PathBuf] {
+        &self.files
+    }
+
+    fn merge_layer(
+        &mut self,
+        table: &mut toml::Table,
+        layer: toml::Table,
+        source: Source,
+        origin: Option<&Path>,
+    ) {
+        for key in layer.keys() {
+            self.sources.insert(key.clone(), source);
+            match origin {
+                Some(path) => self.origins.insert(key.clone(), path.to_path_buf()),
+                None => self.origins.remove(key),
+            };
+        }
+        merge_tables(table, layer);
+    }
+
+    /// Like [`pick`], but reports whether a configured value came from the
+    /// environment or the config file.
+    pub fn pick<T>(
+        &self,
+        key: &str,
+        cli: Option<T>,
+        configured: Option<T>,
+        default: Option<T>,
+    ) -> Option<(T, Source)> {
+        pick(cli, configured, default).map(|(v, source)| match source {
+            Source::File => (v, self.source_of(key)),
+            other => (v, other),
+        })
+    }
 }
 
 fn expand_home(path: &str) -> PathBuf {
@@ -28,27 +322,402 @@ fn expand_home(path: &str) -> PathBuf {
     PathBuf::from(path)
 }
 
-pub fn default_config_path() -> PathBuf {
-    let home = env::var("HOME").unwrap_or_else(|_| ".".to_string());
-    PathBuf::from(home)
-        .join(".config")
-        .join("geekmagic-stats")
-        .join("config.toml")
+/// Directory holding the system-wide config and its `conf.d` drop-ins.
+pub const SYSTEM_CONFIG_DIR: &str = "/etc/geekmagic-stats";
+
+const CONFIG_FILE_NAME: &str = "config.toml";
+const DROP_IN_DIR: &str = "conf.d";
+
+/// Per-user config directory: `$XDG_CONFIG_HOME/geekmagic-stats`, falling back
+/// to `~/.config/geekmagic-stats`. `None` when neither variable is usable, as
+/// under some service managers.
+pub fn user_config_dir() -> Option<PathBuf> {
+    let xdg = env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .filter(|p| p.is_absolute());
+    let home = || {
+        env::var_os("HOME")
+            .filter(|h| !h.is_empty())
+            .map(|h| PathBuf::from(h).join(".config"))
+    };
+    xdg.or_else(home).map(|dir| dir.join("geekmagic-stats"))
+}
+
+/// Per-user state directory: `$XDG_STATE_HOME/geekmagic-stats`, falling back
+/// to `~/.local/state/geekmagic-stats`.
+pub fn user_state_dir() -> Option<PathBuf> {
+    let xdg = env::var_os("XDG_STATE_HOME")
+        .map(PathBuf::from)
+        .filter(|p| p.is_absolute());
+    let home = || {
+        env::var_os("HOME")
+            .filter(|h| !h.is_empty())
+            .map(|h| PathBuf::from(h).join(".local/state"))
+    };
+    xdg.or_else(home).map(|dir| dir.join("geekmagic-stats"))
+}
+
+pub fn default_config_path() -> Option<PathBuf> {
+    user_config_dir().map(|dir| dir.join(CONFIG_FILE_NAME))
 }
 
-pub fn load(path_override: Option<&str>) -> Result<AppConfig> {
-    let path = path_override
+pub fn resolve_path(path_override: Option<&str>) -> Option<PathBuf> {
+    path_override
         .map(expand_home)
-        .unwrap_or_else(default_config_path);
-    load_from_path(&path)
+        .or_else(|| env::var(ENV_CONFIG_PATH).ok().map(|p| expand_home(&p)))
+        .or_else(default_config_path)
 }
 
-fn load_from_path(path: &Path) -> Result<AppConfig> {
+/// `*.toml` fragments in a `conf.d` directory, in lexical order.
+fn drop_ins(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.extension().is_some_and(|ext| ext == "toml") && p.is_file())
+        .collect();
+    paths.sort();
+    paths
+}
+
+/// Where the config layers are read from.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConfigPaths {
+    /// Directory with the system-wide `config.toml` and its `conf.d`; `None`
+    /// when a file was named explicitly.
+    pub system_dir: Option<PathBuf>,
+    /// The user's file: `--config`, `GEEKMAGIC_CONFIG` or the XDG default.
+    pub user_file: Option<PathBuf>,
+    /// Whether `conf.d` next to `user_file` is read. Only the default XDG
+    /// location has drop-ins; a file named explicitly stands alone.
+    pub user_drop_ins: bool,
+}
+
+impl ConfigPaths {
+    /// A file named with `--config` or `GEEKMAGIC_CONFIG` is read on its own;
+    /// otherwise the system layers come first, then the XDG config and its
+    /// drop-ins.
+    pub fn resolve(path_override: Option<&str>) -> ConfigPaths {
+        let explicit = path_override
+            .map(expand_home)
+            .or_else(|| env::var(ENV_CONFIG_PATH).ok().map(|p| expand_home(&p)));
+        match explicit {
+            Some(path) => ConfigPaths {
+                system_dir: None,
+                user_file: Some(path),
+                user_drop_ins: false,
+            },
+            None => ConfigPaths {
+                system_dir: Some(PathBuf::from(SYSTEM_CONFIG_DIR)),
+                user_file: default_config_path(),
+                user_drop_ins: true,
+            },
+        }
+    }
+
+    /// `conf.d` directories that are read.
+    fn drop_in_dirs(&self) -> Vec<PathBuf> {
+        let system = self.system_dir.as_ref().map(|dir| dir.join(DROP_IN_DIR));
+        let user = self
+            .user_file
+            .as_deref()
+            .and_then(Path::parent)
+            .filter(|_| self.user_drop_ins)
+            .map(|dir| dir.join(DROP_IN_DIR));
+        system.into_iter().chain(user).collect()
+    }
+
+    /// Config files in merge order, lowest precedence first: the system
+    /// file, system drop-ins, the user file, then drop-ins next to the user
+    /// file. Main files are listed even when missing.
+    pub fn layers(&self) -> Vec<PathBuf> {
+        let mut paths = Vec::new();
+        if let Some(dir) = &self.system_dir {
+            paths.push(dir.join(CONFIG_FILE_NAME));
+            paths.extend(drop_ins(&dir.join(DROP_IN_DIR)));
+        }
+        if let Some(user) = &self.user_file {
+            paths.push(user.clone());
+            if self.user_drop_ins {
+                if let Some(parent) = user.parent() {
+                    paths.extend(drop_ins(&parent.join(DROP_IN_DIR)));
+                }
+            }
+        }
+        paths
+    }
+}
+
+pub fn load(path_override: Option<&str>, profile: Option<&str>) -> Result<AppConfig> {
+    load_layered(path_override, profile).map(|loaded| loaded.config)
+}
+
+pub fn load_layered(path_override: Option<&str>, profile: Option<&str>) -> Result<LoadedConfig> {
+    let vars: Vec<(String, String)> = env::vars().collect();
+    load_from(&ConfigPaths::resolve(path_override), profile, &vars)
+}
+
+/// Merges the layers in `paths`, the selected profile and the `GEEKMAGIC_*`
+/// variables among `vars`.
+pub fn load_from(
+    paths: &ConfigPaths,
+    profile: Option<&str>,
+    vars: &[(String, String)],
+) -> Result<LoadedConfig> {
+    let mut loaded = LoadedConfig::default();
+    let mut merged = toml::Table::new();
+
+    for path in paths.layers() {
+        if let Some(layer) = load_file_table(&path)? {
+            loaded.merge_layer(&mut merged, layer, Source::File, Some(&path));
+            loaded.files.push(path);
+        }
+    }
+
+    // Profiles sit between the files and the environment, so profile tables
+    // set through the environment must be merged before one is applied.
+    let mut env = env_table(vars, &mut loaded.warnings)?;
+    if let Some(env_profiles) = env.remove("profile") {
+        let layer = toml::Table::from_iter([("profile".to_string(), env_profiles)]);
+        loaded.merge_layer(&mut merged, layer, Source::Env, None);
+    }
+    let profile = profile
+        .map(str::to_string)
+        .or_else(|| {
+            vars.iter()
+                .find(|(name, _)| name == ENV_PROFILE)
+                .map(|(_, value)| value.clone())
+        })
+        .or_else(|| merged.get("default_profile")?.as_str().map(str::to_string));
+    if let Some(name) = &profile {
+        let layer = profile_table(&merged, name)?;
+        loaded.merge_layer(&mut merged, layer, Source::Profile, None);
+    }
+    loaded.merge_layer(&mut merged, env, Source::Env, None);
+
+    loaded.config = AppConfig::deserialize(toml::Value::Table(merged))
+        .context("failed to merge config layers")?;
+    loaded.config.validate()?;
+    loaded.profile = profile;
+    Ok(loaded)
+}
+
+/// Looks up `[profile.<name>]` in the merged table.
+fn profile_table(merged: &toml::Table, name: &str) -> Result<toml::Table> {
+    let profiles = merged.get("profile").and_then(toml::Value::as_table);
+    if let Some(table) = profiles.and_then(|p| p.get(name)?.as_table()) {
+        for nested in ["profile", "default_profile"] {
+            if table.contains_key(nested) {
+                bail!("profile `{name}` can't set `{nested}`");
+            }
+        }
+        return Ok(table.clone());
+    }
+
+    let known: Vec<&str> = profiles
+        .map(|p| p.keys().map(String::as_str).collect())
+        .unwrap_or_default();
+    let hint = closest_match(name, known.iter().copied())
+        .map(|k| format!(" (did you mean `{k}`?)"))
+        .unwrap_or_default();
+    if known.is_empty() {
+        bail!("unknown profile `{name}`; no [profile.*] tables are defined");
+    }
+    bail!(
+        "unknown profile `{name}`{hint}; defined profiles: {}",
+        known.join(", ")
+    )
+}
+
+/// Reads and validates a config file, returning its raw table so it can be
+/// merged with other layers.
+fn load_file_table(path: &Path) -> Result<Option<toml::Table>> {
     if !path.exists() {
-        return Ok(AppConfig::default());
+        return Ok(None);
     }
 
     let raw = fs::read_to_string(path)
         .with_context(|| format!("failed to read config at {}", path.display()))?;
-    toml::from_str(&raw).with_context(|| format!("failed to parse config at {}", path.display()))
+    let context = || format!("failed to parse config at {}", path.display());
+    // Deserialize from the source text first so errors point at the line.
+    toml::from_str::<AppConfig>(&raw)
+        .map_err(|e| anyhow!("{}{}", e.to_string().trim_end(), hint_suffix(e.message())))
+        .with_context(context)?;
+    toml::from_str(&raw).with_context(context).map(Some)
+}
+
+fn hint_suffix(message: &str) -> String {
+    unknown_field_hint(message)
+        .map(|s| format!("\nhelp: did you mean `{s}`?"))
+        .unwrap_or_default()
+}
+
+/// Collects `GEEKMAGIC_*` variables into a config table. Double underscores
+/// separate nested tables, so `GEEKMAGIC_A__B` sets key `b` in table `a`.
+/// Variables that don't name a config key are skipped with a warning, as
+/// other tools may share the prefix; invalid values are errors.
+fn env_table(vars: &[(String, String)], warnings: &mut Vec<String>) -> Result<toml::Table> {
+    let mut table = toml::Table::new();
+    for (name, raw) in vars {
+        let Some(key) = name.strip_prefix(ENV_PREFIX) else {
+            continue;
+        };
+        if name == ENV_CONFIG_PATH || name == ENV_PROFILE || key.is_empty() {
+            continue;
+        }
+        let path: Vec<String> = key.split("__").map(str::to_lowercase).collect();
+        let mut single = toml::Table::new();
+        insert_path(&mut single, &path, parse_env_value(raw));
+
+        // Validate each variable on its own so errors name the culprit.
+        if let Err(e) = AppConfig::deserialize(toml::Value::Table(single.clone())) {
+            let message = e.message().trim_end().to_string();
+            if !message.starts_with("unknown field") {
+                bail!("invalid {name}: {message}");
+            }
+            let hint = unknown_field_hint(&message)
+                .map(|s| {
+                    let parent = name.rsplit_once("__").map_or(ENV_PREFIX, |(p, _)| p);
+                    let sep = if parent == ENV_PREFIX { "" } else { "__" };
+                    format!(" (did you mean `{parent}{sep}{}`?)", s.to_uppercase())
+                })
+                .unwrap_or_default();
+            warnings.push(format!("ignoring {name}: not a config key{hint}"));
+            continue;
+        }
+        merge_tables(&mut table, single);
+    }
+    Ok(table)
+}
+
+fn insert_path(table: &mut toml::Table, path: &[String], value: toml::Value) {
+    let Some((last, parents)) = path.split_last() else {
+        return;
+    };
+    let mut current = table;
+    for part in parents {
+        let entry = current
+            .entry(part.clone())
+            .or_insert_with(|| toml::Value::Table(toml::Table::new()));
+        if !entry.is_table() {
+            *entry = toml::Value::Table(toml::Table::new());
+        }
+        let Some(next) = entry.as_table_mut() else {
+            return;
+        };
+        current = next;
+    }
+    current.insert(last.clone(), value);
+}
+
+/// Interprets an environment value as a TOML literal (`300`, `true`,
+/// `["a", "b"]`), falling back to a plain string.
+fn parse_env_value(raw: &str) -> toml::Value {
+    toml::from_str::<toml::Table>(&format!("v = {raw}"))
+        .ok()
+        .and_then(|mut t| t.remove("v"))
+        .unwrap_or_else(|| toml::Value::String(raw.to_string()))
+}
+
+fn merge_tables(base: &mut toml::Table, over: toml::Table) {
+    for (key, value) in over {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(existing)), toml::Value::Table(incoming)) => {
+                merge_tables(existing, incoming);
+            }
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+/// Suggests the closest known key for serde's "unknown field `x`, expected one
+/// of `a`, `b`" errors.
+fn unknown_field_hint(message: &str) -> Option<String> {
+    let rest = message.strip_prefix("unknown field `")?;
+    let (unknown, expected) = rest.split_once('`')?;
+    let candidates = expected.split('`').skip(1).step_by(2);
+    closest_match(unknown, candidates).map(str::to_string)
+}
+
+fn closest_match<'a>(needle: &str, candidates: impl Iterator<Item = &'a str>) -> Option<&'a str> {
+    candidates
+        .map(|candidate| (strsim::jaro_winkler(needle, candidate), candidate))
+        .filter(|(score, _)| *score > 0.7)
+        .max_by(|a, b| a.0.total_cmp(&b.0))
+        .map(|(_, candidate)| candidate)
+}
+
+/// Writes [`STARTER_CONFIG`] to `path`, creating parent directories.
+pub fn init(path: &Path, force: bool) -> Result<()> {
+    if path.exists() && !force {
+        bail!(
+            "{} already exists; pass --force to overwrite",
+            path.display()
+        );
+    }
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create {}", parent.display()))?;
+    }
+    fs::write(path, STARTER_CONFIG)
+        .with_context(|| format!("failed to write config at {}", path.display()))
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// Files and `conf.d` directories to watch, with their modification times.
+/// Directory mtimes change when fragments are added or removed.
+fn fingerprint(paths: &ConfigPaths) -> Vec<(PathBuf, Option<SystemTime>)> {
+    let mut watched = paths.layers();
+    watched.extend(paths.drop_in_dirs());
+    watched
+        .into_iter()
+        .map(|path| {
+            let modified = modified_time(&path);
+            (path, modified)
+        })
+        .collect()
+}
+
+/// Detects edits to any config layer by polling modification times.
+pub struct ConfigWatcher {
+    paths: ConfigPaths,
+    profile: Option<String>,
+    last_seen: Vec<(PathBuf, Option<SystemTime>)>,
+}
+
+impl ConfigWatcher {
+    pub fn new(path_override: Option<&str>, profile: Option<&str>) -> Self {
+        Self::watch(ConfigPaths::resolve(path_override), profile)
+    }
+
+    /// Watches the layers in `paths`, starting from their current state.
+    pub fn watch(paths: ConfigPaths, profile: Option<&str>) -> Self {
+        let last_seen = fingerprint(&paths);
+        Self {
+            paths,
+            profile: profile.map(str::to_string),
+            last_seen,
+        }
+    }
+
+    /// Re-reads the config if any layer changed since the last poll.
+    ///
+    /// Returns `None` when nothing changed. A parse error is returned as
+    /// `Some(Err(..))` so the caller can keep its previous config.
+    pub fn poll(&mut self) -> Option<Result<AppConfig>> {
+        let current = fingerprint(&self.paths);
+        if current == self.last_seen {
+            return None;
+        }
+        self.last_seen = current;
+        let vars: Vec<(String, String)> = env::vars().collect();
+        let loaded = load_from(&self.paths, self.profile.as_deref(), &vars);
+        Some(loaded.map(|loaded| loaded.config))
+    }
 }
@@ -0,0 +1,52 @@
+use anyhow::Result;
+use image::{Rgba, RgbaImage};
+
+use crate::upload::encode_jpeg;
+
+/// 4x4 Bayer threshold matrix, values 0..16.
+const BAYER_4X4: [[u8; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+
+/// Quantizes a channel to `bits` and expands it back to 8 bits the way the
+/// panel does (high bits replicated into the low bits).
+fn quantize(value: u8, bits: u32) -> u8 {
+    let q = value >> (8 - bits);
+    (q << (8 - bits)) | (q >> (2 * bits - 8))
+}
+
+/// The color the RGB565 panel actually shows for `c`.
+pub fn to_rgb565(c: Rgba<u8>) -> Rgba<u8> {
+    Rgba([
+        quantize(c[0], 5),
+        quantize(c[1], 6),
+        quantize(c[2], 5),
+        c[3],
+    ])
+}
+
+/// Ordered-dithers `c` to RGB565 at pixel `(x, y)`, trading gradient banding
+/// for a fine, regular pattern.
+pub fn dither_rgb565(c: Rgba<u8>, x: u32, y: u32) -> Rgba<u8> {
+    // Threshold in 0..1 of a quantization step; `quantize` truncates, so on
+    // average the output matches the input.
+    let t = (BAYER_4X4[(y % 4) as usize][(x % 4) as usize] as f32 + 0.5) / 16.0;
+    let channel = |value: u8, bits: u32| {
+        let step = (1u32 << (8 - bits)) as f32;
+        quantize(
+            (value as f32 + t * step).round().clamp(0.0, 255.0) as u8,
+            bits,
+        )
+    };
+    Rgba([channel(c[0], 5), channel(c[1], 6), channel(c[2], 5), c[3]])
+}
+
+/// Approximates what the device shows: the same lossy JPEG encode used for
+/// uploads, decoded and quantized to RGB565.
+pub fn simulate(img: &RgbaImage) -> Result<RgbaImage> {
+    let jpeg = encode_jpeg(img)?;
+    let mut decoded =
+        image::load_from_memory_with_format(&jpeg, image::ImageFormat::Jpeg)?.into_rgba8();
+    for px in decoded.pixels_mut() {
+        *px = to_rgb565(*px);
+    }
+    Ok(decoded)
+}
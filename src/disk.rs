@@ -1,7 +1,14 @@
+use std::time::Instant;
+
 use anyhow::{anyhow, Result};
 use clap::Parser;
 use geekmagic_common::config;
-use geekmagic_common::disk_render;
+use geekmagic_common::logging::{self, LogArgs};
+use geekmagic_common::preview::{self, PreviewMode};
+use geekmagic_common::report::{Report, UploadResult};
+use geekmagic_common::{device, disk_render, upload};
+use image::RgbaImage;
+use log::{info, warn};
 
 #[derive(Parser)]
 #[command(about = "Render disk usage pie chart to a GeekMagic display")]
@@ -13,36 +20,113 @@ struct Args {
     #[arg(long)]
     config: Option<String>,
 
+    /// Named profile from the config file
+    #[arg(long)]
+    profile: Option<String>,
+
     #[arg(short, long)]
     output: Option<String>,
+
+    /// Draw the screen in the terminal instead of uploading
+    /// (auto, kitty, sixel or blocks)
+    #[arg(long, value_name = "MODE", num_args = 0..=1, default_missing_value = "auto")]
+    preview: Option<PreviewMode>,
+
+    /// Dither gradients to reduce banding on the RGB565 panel
+    #[arg(long)]
+    dither: bool,
+
+    /// Run --output/--preview frames through the device's JPEG + RGB565 pipeline
+    #[arg(long)]
+    simulate_device: bool,
+
+    /// Render, but don't upload anything
+    #[arg(long)]
+    dry_run: bool,
+
+    /// Print the disk info and upload result as JSON
+    #[arg(long, conflicts_with = "preview")]
+    json: bool,
+
+    #[command(flatten)]
+    log: LogArgs,
 }
 
 fn main() -> Result<()> {
     let args = Args::parse();
-    let cfg = config::load(args.config.as_deref())?;
-    let host = args
-        .host
-        .or(cfg.host)
-        .ok_or_else(|| anyhow!("missing host; pass --host or set host in config"))?;
+    let loaded = config::load_layered(args.config.as_deref(), args.profile.as_deref())?;
+    let cfg = loaded.config.clone();
+    let (log_level, log_format) = args
+        .log
+        .resolve(cfg.log_level.as_deref(), cfg.log_format.as_deref())?;
+    logging::init(log_level, log_format);
+    if args.json {
+        logging::stderr_only();
+    }
+    for warning in loaded.warnings() {
+        warn!("{warning}");
+    }
     let info = disk_render::get_disk_info()?;
 
-    println!(
-        "Disk: {} total, {} used, {} free ({:.1}%)",
-        disk_render::format_size(info.total_bytes),
-        disk_render::format_size(info.used_bytes),
-        disk_render::format_size(info.free_bytes),
-        info.free_bytes as f64 / info.total_bytes as f64 * 100.0,
-    );
+    if !args.json {
+        println!(
+            "Disk: {} total, {} used, {} free ({:.1}%)",
+            disk_render::format_size(info.total_bytes),
+            disk_render::format_size(info.used_bytes),
+            disk_render::format_size(info.free_bytes),
+            info.free_bytes as f64 / info.total_bytes as f64 * 100.0,
+        );
+    }
 
-    let img = disk_render::render_disk(&info)?;
+    let dither = args.dither || cfg.dither.unwrap_or(false);
+    let mut img = disk_render::render_disk(&info, dither)?;
+    if args.simulate_device && (args.output.is_some() || args.preview.is_some()) {
+        img = device::simulate(&img)?;
+    }
 
-    if let Some(path) = &args.output {
+    let pushed = if let Some(path) = &args.output {
         img.save(path)?;
-        println!("Saved to {path}");
+        info!("Saved to {path}");
+        Ok(None)
+    } else if let Some(mode) = args.preview {
+        preview::show(&img, mode)?;
+        Ok(None)
+    } else if args.dry_run {
+        Ok(None)
     } else {
-        geekmagic_common::upload::upload_and_display(&host, &img)?;
-        println!("Pushed to {host}");
+        match args.host.or(cfg.host) {
+            Some(host) => push(&host, &img, cfg.brightness).map(Some),
+            // Still print the disk info with --json, next to the error.
+            None => Err(anyhow!("missing host; pass --host or set host in config")),
+        }
+    };
+
+    if args.json {
+        Report {
+            disk: Some(info),
+            upload: pushed.as_ref().ok().cloned().flatten(),
+            error: pushed.as_ref().err().map(|e| format!("{e:#}")),
+            ..Report::default()
+        }
+        .print()?;
     }
+    pushed.map(|_| ())
+}
 
-    Ok(())
+fn push(host: &str, img: &RgbaImage, brightness: Option<u8>) -> Result<UploadResult> {
+    let started = Instant::now();
+    let bytes = upload::upload_and_display(host, img)?;
+    info!(bytes, upload_ms = started.elapsed().as_millis() as u64; "Pushed to {host}");
+    if let Some(brightness) = brightness {
+        // The image is already up; a failed brightness call shouldn't undo that.
+        if let Err(e) = upload::set_brightness(host, brightness) {
+            warn!("{e:#}");
+        }
+    }
+    Ok(UploadResult {
+        host: host.to_string(),
+        pages: vec!["disk".to_string()],
+        bytes,
+        seconds: started.elapsed().as_secs_f64(),
+    })
 }
@@ -5,15 +5,12 @@ use ab_glyph::{FontRef, PxScale};
 use anyhow::{Context, Result};
 use image::{Rgba, RgbaImage};
 use imageproc::drawing::draw_text_mut;
+use serde::Serialize;
 
-const W: u32 = 240;
-const H: u32 = 240;
-
-const BG: Rgba<u8> = Rgba([12, 12, 16, 255]);
-const TEXT_PRIMARY: Rgba<u8> = Rgba([240, 240, 245, 255]);
-const TEXT_DIM: Rgba<u8> = Rgba([113, 113, 122, 255]);
-const TEXT_MUTED: Rgba<u8> = Rgba([161, 161, 170, 255]);
-const SEPARATOR: Rgba<u8> = Rgba([35, 35, 45, 255]);
+use crate::device;
+use crate::render::{
+    BG, FONT_BOLD_BYTES, FONT_BYTES, H, SEPARATOR, TEXT_DIM, TEXT_MUTED, TEXT_PRIMARY, W,
+};
 
 const PIE_USED: Rgba<u8> = Rgba([99, 102, 241, 255]);
 const PIE_USED_2: Rgba<u8> = Rgba([139, 92, 246, 255]);
@@ -21,9 +18,7 @@ const PIE_FREE: Rgba<u8> = Rgba([34, 197, 94, 255]);
 const PIE_FREE_2: Rgba<u8> = Rgba([16, 185, 129, 255]);
 const PIE_BG: Rgba<u8> = Rgba([30, 30, 40, 255]);
 
-const FONT_BYTES: &[u8] = include_bytes!("../fonts/Inter-Regular.ttf");
-const FONT_BOLD_BYTES: &[u8] = include_bytes!("../fonts/Inter-Bold.ttf");
-
+#[derive(Debug, Clone, Serialize)]
 pub struct DiskInfo {
     pub total_bytes: u64,
     pub free_bytes: u64,
@@ -179,8 +174,8 @@ fn is_inside_rounded(px: u32, py: u32, w: u32, h: u32, r: u32) -> bool {
             py > h.saturating_sub(r + 1)
         };
         if in_corner_x && in_corner_y {
-            let dx = if px < cx { cx - px } else { px - cx };
-            let dy = if py < cy { cy - py } else { py - cy };
+            let dx = cx.abs_diff(px);
+            let dy = cy.abs_diff(py);
             if dx * dx + dy * dy > r * r {
                 return false;
             }
@@ -189,7 +184,7 @@ fn is_inside_rounded(px: u32, py: u32, w: u32, h: u32, r: u32) -> bool {
     true
 }
 
-pub fn render_disk(info: &DiskInfo) -> Result<RgbaImage> {
+pub fn render_disk(info: &DiskInfo, dither: bool) -> Result<RgbaImage> {
     let font = FontRef::try_from_slice(FONT_BYTES)?;
     let font_bold = FontRef::try_from_slice(FONT_BOLD_BYTES)?;
     let mut img = RgbaImage::from_pixel(W, H, BG);
@@ -266,6 +261,11 @@ pub fn render_disk(info: &DiskInfo) -> Result<RgbaImage> {
                 );
 
                 let blended = lerp_color(BG, lit, aa);
+                let blended = if dither {
+                    device::dither_rgb565(blended, px, py)
+                } else {
+                    blended
+                };
                 img.put_pixel(px, py, blended);
             } else if dist < pie_r_inner && dist >= pie_r_inner - 1.0 {
                 let aa = (pie_r_inner - dist).clamp(0.0, 1.0) as f32;
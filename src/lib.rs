@@ -1,3 +1,21 @@
+pub mod alert;
+pub mod budget;
 pub mod config;
+pub mod device;
 pub mod disk_render;
+pub mod http;
+pub mod logging;
+pub mod metrics;
+pub mod network_render;
+pub mod pace;
+pub mod preview;
+pub mod render;
+pub mod report;
+pub mod schedule;
+pub mod sensors_render;
+pub mod source;
+pub mod stats;
+pub mod status;
+pub mod system_render;
+pub mod telemetry;
 pub mod upload;
@@ -0,0 +1,239 @@
+use std::fmt::{self, Write as _};
+use std::io::{self, Write};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use anyhow::{bail, Result};
+use log::kv::{self, Key, Value, VisitSource, VisitValue};
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use serde_json::{Map, Number};
+
+/// How log records are written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogFormat {
+    /// `[HH:MM:SS] message key=value`, for terminals and plain log files.
+    Text,
+    /// One JSON object per line, for journald, launchd or log shippers.
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Ok(match s {
+            "text" => LogFormat::Text,
+            "json" => LogFormat::Json,
+            other => bail!("unknown log format `{other}` (expected text or json)"),
+        })
+    }
+}
+
+impl fmt::Display for LogFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            LogFormat::Text => "text",
+            LogFormat::Json => "json",
+        })
+    }
+}
+
+/// Verbosity and format flags shared by both binaries.
+#[derive(Debug, Clone, clap::Args)]
+pub struct LogArgs {
+    /// More output: -v adds phase timings and per-page sizes, -vv everything
+    #[arg(short, long, action = clap::ArgAction::Count, conflicts_with = "quiet")]
+    pub verbose: u8,
+
+    /// Only log warnings and errors
+    #[arg(short, long)]
+    pub quiet: bool,
+
+    /// Log format: text or json
+    #[arg(long, value_name = "FORMAT")]
+    pub log_format: Option<LogFormat>,
+}
+
+impl LogArgs {
+    /// The level from the flags, if any was given.
+    pub fn level(&self) -> Option<LevelFilter> {
+        match (self.quiet, self.verbose) {
+            (true, _) => Some(LevelFilter::Warn),
+            (false, 0) => None,
+            (false, 1) => Some(LevelFilter::Debug),
+            (false, _) => Some(LevelFilter::Trace),
+        }
+    }
+
+    /// Level and format from the flags, falling back to the `log_level` and
+    /// `log_format` settings, then to info and text.
+    pub fn resolve(
+        &self,
+        level: Option<&str>,
+        format: Option<&str>,
+    ) -> Result<(LevelFilter, LogFormat)> {
+        let level = match (self.level(), level) {
+            (Some(level), _) => level,
+            (None, Some(level)) => parse_level(level)?,
+            (None, None) => LevelFilter::Info,
+        };
+        let format = match (self.log_format, format) {
+            (Some(format), _) => format,
+            (None, Some(format)) => format.parse()?,
+            (None, None) => LogFormat::Text,
+        };
+        Ok((level, format))
+    }
+}
+
+/// Parses a `log_level` setting.
+pub fn parse_level(s: &str) -> Result<LevelFilter> {
+    match LevelFilter::from_str(s) {
+        Ok(level) => Ok(level),
+        Err(_) => {
+            bail!("unknown log level `{s}` (expected off, error, warn, info, debug or trace)")
+        }
+    }
+}
+
+struct Logger {
+    json: AtomicBool,
+    stderr_only: AtomicBool,
+}
+
+static LOGGER: Logger = Logger {
+    json: AtomicBool::new(false),
+    stderr_only: AtomicBool::new(false),
+};
+
+/// Installs the logger, or updates level and format if it already is (on
+/// config reload).
+pub fn init(level: LevelFilter, format: LogFormat) {
+    // Only fails when already installed, which is fine.
+    let _ = log::set_logger(&LOGGER);
+    log::set_max_level(level);
+    LOGGER
+        .json
+        .store(format == LogFormat::Json, Ordering::Relaxed);
+}
+
+/// Sends every record to stderr, keeping stdout for `--json` output.
+pub fn stderr_only() {
+    LOGGER.stderr_only.store(true, Ordering::Relaxed);
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        // -v is about this tool; dependencies' debug output (connection
+        // pools, TLS) only shows up at trace.
+        let own = metadata.target().starts_with("geekmagic");
+        metadata.level() <= log::max_level()
+            && (own || metadata.level() <= Level::Info || log::max_level() == LevelFilter::Trace)
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let line = if self.json.load(Ordering::Relaxed) {
+            json_line(record)
+        } else {
+            text_line(record)
+        };
+        // Warnings and errors go to stderr, like the messages they replace.
+        let _ = if record.level() <= Level::Warn || self.stderr_only.load(Ordering::Relaxed) {
+            writeln!(io::stderr().lock(), "{line}")
+        } else {
+            writeln!(io::stdout().lock(), "{line}")
+        };
+    }
+
+    fn flush(&self) {
+        let _ = io::stdout().flush();
+    }
+}
+
+struct TextFields<'a>(&'a mut String);
+
+impl<'kvs> VisitSource<'kvs> for TextFields<'_> {
+    fn visit_pair(&mut self, key: Key<'kvs>, value: Value<'kvs>) -> Result<(), kv::Error> {
+        let value = value.to_string();
+        let _ = if value.contains(char::is_whitespace) {
+            write!(self.0, " {key}={value:?}")
+        } else {
+            write!(self.0, " {key}={value}")
+        };
+        Ok(())
+    }
+}
+
+fn text_line(record: &Record) -> String {
+    let mut line = format!("[{}] ", chrono::Local::now().format("%H:%M:%S"));
+    if record.level() != Level::Info {
+        let _ = write!(line, "{} ", record.level());
+    }
+    let _ = write!(line, "{}", record.args());
+    let _ = record.key_values().visit(&mut TextFields(&mut line));
+    line
+}
+
+struct JsonValue(Option<serde_json::Value>);
+
+impl<'v> VisitValue<'v> for JsonValue {
+    fn visit_any(&mut self, value: Value) -> Result<(), kv::Error> {
+        self.0 = Some(serde_json::Value::String(value.to_string()));
+        Ok(())
+    }
+
+    fn visit_u64(&mut self, value: u64) -> Result<(), kv::Error> {
+        self.0 = Some(value.into());
+        Ok(())
+    }
+
+    fn visit_i64(&mut self, value: i64) -> Result<(), kv::Error> {
+        self.0 = Some(value.into());
+        Ok(())
+    }
+
+    fn visit_f64(&mut self, value: f64) -> Result<(), kv::Error> {
+        self.0 = Number::from_f64(value).map(serde_json::Value::Number);
+        Ok(())
+    }
+
+    fn visit_bool(&mut self, value: bool) -> Result<(), kv::Error> {
+        self.0 = Some(value.into());
+        Ok(())
+    }
+
+    fn visit_str(&mut self, value: &str) -> Result<(), kv::Error> {
+        self.0 = Some(value.into());
+        Ok(())
+    }
+}
+
+struct JsonFields<'a>(&'a mut Map<String, serde_json::Value>);
+
+impl<'kvs> VisitSource<'kvs> for JsonFields<'_> {
+    fn visit_pair(&mut self, key: Key<'kvs>, value: Value<'kvs>) -> Result<(), kv::Error> {
+        let mut visitor = JsonValue(None);
+        value.visit(&mut visitor)?;
+        self.0.insert(
+            key.to_string(),
+            visitor.0.unwrap_or(serde_json::Value::Null),
+        );
+        Ok(())
+    }
+}
+
+fn json_line(record: &Record) -> String {
+    let mut object = Map::new();
+    object.insert("time".into(), chrono::Utc::now().to_rfc3339().into());
+    object.insert(
+        "level".into(),
+        record.level().as_str().to_lowercase().into(),
+    );
+    object.insert("target".into(), record.target().into());
+    object.insert("message".into(), record.args().to_string().into());
+    let _ = record.key_values().visit(&mut JsonFields(&mut object));
+    serde_json::Value::Object(object).to_string()
+}
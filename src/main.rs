@@ -1,13 +1,25 @@
-mod render;
-mod stats;
-
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
-use anyhow::{anyhow, Result};
-use clap::Parser;
-use geekmagic_common::config;
-use geekmagic_common::disk_render;
+use anyhow::{anyhow, bail, Result};
+use clap::{Parser, Subcommand};
+use geekmagic_common::alert::{self, AlertRule, Alerter};
+use geekmagic_common::config::{self, AppConfig, ConfigPaths, ConfigWatcher, LoadedConfig, Source};
+use geekmagic_common::logging::{self, LogArgs, LogFormat};
+use geekmagic_common::network_render::{self, NetworkMonitor};
+use geekmagic_common::pace::{self, History, PaceModel};
+use geekmagic_common::preview::{self, PreviewMode};
+use geekmagic_common::report::{Report, UploadResult};
+use geekmagic_common::schedule::{self, WorkingHours};
+use geekmagic_common::sensors_render;
+use geekmagic_common::source::{self, UsageSource};
+use geekmagic_common::system_render::{self, SystemMonitor};
+use geekmagic_common::upload::{self, AlbumSettings};
+use geekmagic_common::{budget, device, disk_render, metrics, render, stats, status, telemetry};
+use image::RgbaImage;
+use log::{debug, error, info, warn, LevelFilter};
 
 #[derive(Parser)]
 #[command(about = "Render Claude Code usage stats to a GeekMagic display")]
@@ -20,10 +32,32 @@ struct Args {
     #[arg(long)]
     config: Option<String>,
 
+    /// Named profile from the config file
+    #[arg(long)]
+    profile: Option<String>,
+
+    /// Usage data source: claude-code-stats, stdin, file:PATH, command:CMD or an http(s) URL
+    #[arg(long)]
+    source: Option<String>,
+
     /// Save rendered image to this path instead of uploading
     #[arg(short, long)]
     output: Option<String>,
 
+    /// Draw rendered screens in the terminal instead of uploading
+    /// (auto, kitty, sixel or blocks); redraws each tick with --daemon
+    #[arg(long, value_name = "MODE", num_args = 0..=1, default_missing_value = "auto")]
+    preview: Option<PreviewMode>,
+
+    /// Fetch and render, but don't upload anything
+    #[arg(long)]
+    dry_run: bool,
+
+    /// Print the fetched usage (with pace), disk info and upload result as JSON;
+    /// always runs once
+    #[arg(long, conflicts_with = "preview")]
+    json: bool,
+
     /// Run as daemon, pushing every N seconds
     #[arg(short, long)]
     daemon: Option<u64>,
@@ -31,78 +65,1070 @@ struct Args {
     /// Also render and upload disk usage screen
     #[arg(long)]
     with_disk: bool,
+
+    /// Also render and upload the daily/hourly budget screen
+    #[arg(long)]
+    with_budget: bool,
+
+    /// Also render and upload the CPU, memory and load screen
+    #[arg(long)]
+    with_system: bool,
+
+    /// Also render and upload the network throughput screen
+    #[arg(long)]
+    with_network: bool,
+
+    /// Also render and upload the temperature and fan screen
+    #[arg(long)]
+    with_sensors: bool,
+
+    /// Dither gradients to reduce banding on the RGB565 panel
+    #[arg(long)]
+    dither: bool,
+
+    /// Serve Prometheus metrics on this address in daemon mode (e.g. 127.0.0.1:9184)
+    #[arg(long, value_name = "ADDR")]
+    metrics: Option<String>,
+
+    /// Serve the local status API on this address in daemon mode (e.g. 127.0.0.1:9185)
+    #[arg(long, value_name = "ADDR")]
+    status_addr: Option<String>,
+
+    /// Run --output/--preview frames through the device's JPEG + RGB565 pipeline
+    #[arg(long)]
+    simulate_device: bool,
+
+    #[command(flatten)]
+    log: LogArgs,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Inspect or create the config file
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+    /// Show what a running daemon last fetched and pushed
+    Status {
+        /// Print the raw JSON document
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum ConfigAction {
+    /// Validate the config and print the effective settings with their source
+    Check,
+    /// Write a commented starter config file
+    Init {
+        /// Overwrite an existing file
+        #[arg(long)]
+        force: bool,
+    },
 }
 
 #[derive(Clone)]
 struct RuntimeArgs {
     host: String,
+    source: Arc<dyn UsageSource>,
     output: Option<String>,
+    preview: Option<PreviewMode>,
+    dry_run: bool,
+    json: bool,
     daemon: Option<u64>,
     with_disk: bool,
+    with_budget: bool,
+    with_system: bool,
+    with_network: bool,
+    network_interfaces: Option<Vec<String>>,
+    with_sensors: bool,
+    sensors: Option<Vec<String>>,
+    temp_warn: Option<f64>,
+    temp_critical: Option<f64>,
+    brightness: Option<u8>,
+    dither: bool,
+    stale_after: u64,
+    pace_model: PaceModel,
+    working_hours: Option<WorkingHours>,
+    alerts: Vec<AlertRule>,
+    alert_page: bool,
+    metrics: Option<String>,
+    status_addr: Option<String>,
+    log_level: LevelFilter,
+    log_format: LogFormat,
+    simulate_device: bool,
 }
 
-fn resolve_args(args: Args) -> Result<RuntimeArgs> {
-    let cfg = config::load(args.config.as_deref())?;
-    let host = args
-        .host
-        .or(cfg.host)
-        .ok_or_else(|| anyhow!("missing host; pass --host or set host in config"))?;
+fn resolve_args(args: &Args, cfg: AppConfig) -> Result<RuntimeArgs> {
+    let host = match config::pick(args.host.clone(), cfg.host, None) {
+        Some((host, _)) => host,
+        // Nothing is uploaded when saving to a file, previewing or dry-running.
+        None if args.output.is_some() || args.preview.is_some() || args.dry_run => String::new(),
+        None => bail!("missing host; pass --host or set host in config"),
+    };
+    // A --json run prints one report, so it never turns into a daemon.
+    let daemon = config::pick(args.daemon, cfg.daemon, None)
+        .map(|(v, _)| v)
+        .filter(|_| !args.json);
+    let (with_disk, _) =
+        config::pick(args.with_disk.then_some(true), cfg.with_disk, Some(false)).unwrap();
+    let (with_budget, _) = config::pick(
+        args.with_budget.then_some(true),
+        cfg.with_budget,
+        Some(false),
+    )
+    .unwrap();
+    let (with_system, _) = config::pick(
+        args.with_system.then_some(true),
+        cfg.with_system,
+        Some(false),
+    )
+    .unwrap();
+    let (with_network, _) = config::pick(
+        args.with_network.then_some(true),
+        cfg.with_network,
+        Some(false),
+    )
+    .unwrap();
+    let (with_sensors, _) = config::pick(
+        args.with_sensors.then_some(true),
+        cfg.with_sensors,
+        Some(false),
+    )
+    .unwrap();
+    let (source_spec, _) = config::pick(
+        args.source.clone(),
+        cfg.source,
+        Some(source::DEFAULT_SOURCE.to_string()),
+    )
+    .unwrap();
+    let (dither, _) = config::pick(args.dither.then_some(true), cfg.dither, Some(false)).unwrap();
+    let (stale_after, _) = config::pick(
+        None,
+        cfg.stale_after,
+        Some(render::DEFAULT_STALE_AFTER_MINUTES),
+    )
+    .unwrap();
+    let (pace_model, _) = config::pick(None, cfg.pace_model, Some("linear".to_string())).unwrap();
+    let (alert_page, _) = config::pick(None, cfg.alert_page, Some(true)).unwrap();
+    let metrics = config::pick(args.metrics.clone(), cfg.metrics, None).map(|(v, _)| v);
+    let status_addr = config::pick(args.status_addr.clone(), cfg.status_addr, None).map(|(v, _)| v);
+    let (log_level, log_format) = args
+        .log
+        .resolve(cfg.log_level.as_deref(), cfg.log_format.as_deref())?;
+    let working_hours = WorkingHours::from_settings(
+        cfg.working_hours.as_deref(),
+        cfg.working_days.as_deref(),
+        cfg.timezone.as_deref(),
+    )?;
+    alert::validate(&cfg.alerts)?;
+    if let (Some(warn), Some(critical)) = (cfg.temp_warn, cfg.temp_critical) {
+        if warn > critical {
+            bail!("temp_warn ({warn}) must not be above temp_critical ({critical})");
+        }
+    }
 
     Ok(RuntimeArgs {
         host,
-        output: args.output,
-        daemon: args.daemon.or(cfg.daemon),
-        with_disk: if args.with_disk {
-            true
-        } else {
-            cfg.with_disk.unwrap_or(false)
-        },
+        source: source::parse(&source_spec)?,
+        output: args.output.clone(),
+        preview: args.preview,
+        dry_run: args.dry_run,
+        json: args.json,
+        daemon,
+        with_disk,
+        with_budget,
+        with_system,
+        with_network,
+        network_interfaces: cfg.network_interfaces,
+        with_sensors,
+        sensors: cfg.sensors,
+        temp_warn: cfg.temp_warn,
+        temp_critical: cfg.temp_critical,
+        brightness: cfg.brightness,
+        dither,
+        stale_after,
+        pace_model: pace_model.parse()?,
+        working_hours,
+        alerts: cfg.alerts,
+        alert_page,
+        metrics,
+        status_addr,
+        log_level,
+        log_format,
+        simulate_device: args.simulate_device,
     })
 }
 
-fn run_once(args: &RuntimeArgs) -> Result<()> {
-    let payload = stats::fetch_stats()?;
-    let stats_img = render::render_bars(&payload)?;
+/// Width of the key column in `config check`, fitting the longest key.
+const KEY_WIDTH: usize = 18;
+
+fn print_setting<T: std::fmt::Debug>(
+    loaded: &LoadedConfig,
+    key: &str,
+    setting: Option<(T, Source)>,
+) {
+    match setting {
+        Some((value, source)) => {
+            let value = format!("{value:?}");
+            let source = match (source, loaded.origin(key)) {
+                (Source::File, Some(path)) => format!("{source}: {}", path.display()),
+                (Source::Profile, _) => format!("{source}: {}", loaded.profile().unwrap_or("?")),
+                _ => source.to_string(),
+            };
+            println!("  {key:<KEY_WIDTH$} = {value:<20} ({source})");
+        }
+        None => println!("  {key:<KEY_WIDTH$} = {:<20} (unset)", "-"),
+    }
+}
+
+fn config_check(args: &Args) -> Result<()> {
+    let paths = ConfigPaths::resolve(args.config.as_deref());
+    let vars: Vec<(String, String)> = std::env::vars().collect();
+    let loaded = config::load_from(&paths, args.profile.as_deref(), &vars)?;
+    let cfg = loaded.config.clone();
+
+    println!("Config files (lowest precedence first):");
+    for path in paths.layers() {
+        let state = if loaded.files().contains(&path) {
+            "ok"
+        } else {
+            "not found"
+        };
+        println!("  {} ({state})", path.display());
+    }
+    if paths.user_file.is_none() {
+        println!("  (no user config: neither XDG_CONFIG_HOME nor HOME is set)");
+    }
+    println!();
+    if let Some(profile) = loaded.profile() {
+        println!("Profile: {profile}");
+        println!();
+    }
+
+    let host = loaded.pick("host", args.host.clone(), cfg.host, None);
+    let missing_host = host.is_none();
+    print_setting(&loaded, "host", host);
+    print_setting(
+        &loaded,
+        "daemon",
+        loaded.pick("daemon", args.daemon, cfg.daemon, None),
+    );
+    print_setting(
+        &loaded,
+        "with_disk",
+        loaded.pick(
+            "with_disk",
+            args.with_disk.then_some(true),
+            cfg.with_disk,
+            Some(false),
+        ),
+    );
+    print_setting(
+        &loaded,
+        "with_budget",
+        loaded.pick(
+            "with_budget",
+            args.with_budget.then_some(true),
+            cfg.with_budget,
+            Some(false),
+        ),
+    );
+    print_setting(
+        &loaded,
+        "with_system",
+        loaded.pick(
+            "with_system",
+            args.with_system.then_some(true),
+            cfg.with_system,
+            Some(false),
+        ),
+    );
+    print_setting(
+        &loaded,
+        "with_network",
+        loaded.pick(
+            "with_network",
+            args.with_network.then_some(true),
+            cfg.with_network,
+            Some(false),
+        ),
+    );
+    print_setting(
+        &loaded,
+        "network_interfaces",
+        loaded.pick("network_interfaces", None, cfg.network_interfaces, None),
+    );
+    print_setting(
+        &loaded,
+        "with_sensors",
+        loaded.pick(
+            "with_sensors",
+            args.with_sensors.then_some(true),
+            cfg.with_sensors,
+            Some(false),
+        ),
+    );
+    print_setting(
+        &loaded,
+        "sensors",
+        loaded.pick("sensors", None, cfg.sensors, None),
+    );
+    print_setting(
+        &loaded,
+        "temp_warn",
+        loaded.pick("temp_warn", None, cfg.temp_warn, None),
+    );
+    print_setting(
+        &loaded,
+        "temp_critical",
+        loaded.pick("temp_critical", None, cfg.temp_critical, None),
+    );
+    let source_setting = loaded.pick(
+        "source",
+        args.source.clone(),
+        cfg.source,
+        Some(source::DEFAULT_SOURCE.to_string()),
+    );
+    let source_error = source_setting
+        .as_ref()
+        .and_then(|(spec, _)| source::parse(spec).err());
+    print_setting(&loaded, "source", source_setting);
+    print_setting(
+        &loaded,
+        "brightness",
+        loaded.pick("brightness", None, cfg.brightness, None),
+    );
+    print_setting(
+        &loaded,
+        "dither",
+        loaded.pick(
+            "dither",
+            args.dither.then_some(true),
+            cfg.dither,
+            Some(false),
+        ),
+    );
+    print_setting(
+        &loaded,
+        "stale_after",
+        loaded.pick(
+            "stale_after",
+            None,
+            cfg.stale_after,
+            Some(render::DEFAULT_STALE_AFTER_MINUTES),
+        ),
+    );
+    print_setting(
+        &loaded,
+        "pace_model",
+        loaded.pick(
+            "pace_model",
+            None,
+            cfg.pace_model,
+            Some("linear".to_string()),
+        ),
+    );
+    print_setting(
+        &loaded,
+        "working_hours",
+        loaded.pick("working_hours", None, cfg.working_hours, None),
+    );
+    print_setting(
+        &loaded,
+        "working_days",
+        loaded.pick("working_days", None, cfg.working_days, None),
+    );
+    print_setting(
+        &loaded,
+        "timezone",
+        loaded.pick("timezone", None, cfg.timezone, None),
+    );
+
+    print_setting(
+        &loaded,
+        "alert_page",
+        loaded.pick("alert_page", None, cfg.alert_page, Some(true)),
+    );
+    print_setting(
+        &loaded,
+        "metrics",
+        loaded.pick("metrics", args.metrics.clone(), cfg.metrics, None),
+    );
+    print_setting(
+        &loaded,
+        "status_addr",
+        loaded.pick(
+            "status_addr",
+            args.status_addr.clone(),
+            cfg.status_addr,
+            None,
+        ),
+    );
+    print_setting(
+        &loaded,
+        "log_level",
+        loaded.pick(
+            "log_level",
+            args.log.level().map(|l| l.to_string().to_lowercase()),
+            cfg.log_level,
+            Some("info".to_string()),
+        ),
+    );
+    print_setting(
+        &loaded,
+        "log_format",
+        loaded.pick(
+            "log_format",
+            args.log.log_format.map(|f| f.to_string()),
+            cfg.log_format,
+            Some("text".to_string()),
+        ),
+    );
+    let alert_count = loaded.config.alerts.len();
+    println!(
+        "  {:<KEY_WIDTH$} = {:<20} ({})",
+        "alerts",
+        format!("{alert_count} rule(s)"),
+        if alert_count == 0 { "unset" } else { "config" }
+    );
+    let alert_error = alert::validate(&loaded.config.alerts).err();
+
+    for warning in loaded.warnings() {
+        println!();
+        println!("warning: {warning}");
+    }
+    if missing_host {
+        println!();
+        println!("warning: host is not set; uploads will fail unless --output is used");
+    }
+    if let Some(e) = source_error.or(alert_error) {
+        bail!(e);
+    }
+    Ok(())
+}
+
+fn config_init(args: &Args, force: bool) -> Result<()> {
+    let path = config::resolve_path(args.config.as_deref()).ok_or_else(|| {
+        anyhow!("no user config directory; set XDG_CONFIG_HOME or HOME, or pass --config")
+    })?;
+    config::init(&path, force)?;
+    println!("Wrote {}", path.display());
+    Ok(())
+}
+
+/// What the daemon remembers between updates.
+#[derive(Default)]
+struct State {
+    /// `updated_at` of the last active payload, shown on status screens.
+    last_good: Option<String>,
+    alerter: Alerter,
+    /// Set while the alert page has taken over the display, holding the
+    /// slideshow settings to restore afterwards.
+    preempted: Option<AlbumSettings>,
+    /// Recorded utilization samples, loaded on first use by a pace model
+    /// other than linear.
+    history: Option<History>,
+    /// CPU, memory and load readings for the system page.
+    system: SystemMonitor,
+    /// Interface counters and rates for the network pages.
+    network: NetworkMonitor,
+    /// Host and brightness last set on the device.
+    brightness_sent: Option<(String, u8)>,
+    /// Shared with the metrics and status endpoints.
+    telemetry: telemetry::Shared,
+}
+
+/// Records `data` in the pace history and re-estimates its run-out times
+/// with the configured model.
+fn apply_pace_model(args: &RuntimeArgs, state: &mut State, data: &mut stats::ActiveData) {
+    if args.pace_model == PaceModel::Linear {
+        return;
+    }
+    let history = state.history.get_or_insert_with(|| {
+        History::load().unwrap_or_else(|e| {
+            // Without a path the broken file is left alone rather than
+            // overwritten.
+            warn!("Pace history unusable, starting over in memory: {e:#}");
+            History::default()
+        })
+    });
+    let now = chrono::Utc::now();
+    history.record(data, now);
+    if let Err(e) = history.save() {
+        warn!("Couldn't save pace history: {e:#}");
+    }
+    pace::apply(args.pace_model, history, data, now);
+}
+
+/// Fires the alerts that newly match `data`. Failures are logged and hooks
+/// time out, so a broken hook never blocks the display update.
+fn check_alerts(args: &RuntimeArgs, state: &mut State, data: &stats::ActiveData) {
+    if state.alerter.rules() != args.alerts.as_slice() {
+        // Rules changed on reload; start over so new thresholds are armed.
+        state.alerter = Alerter::new(args.alerts.clone());
+    }
+    for fired in state.alerter.check(data) {
+        info!(window = fired.window.as_str(), utilization = fired.utilization; "Alert: {}", fired.message);
+        if let Err(e) = alert::dispatch(&fired) {
+            warn!("Alert action failed: {e:#}");
+        }
+    }
+}
+
+fn daemon_status(args: &Args, json: bool) -> Result<()> {
+    let cfg = config::load(args.config.as_deref(), args.profile.as_deref())?;
+    let Some((addr, _)) = config::pick(args.status_addr.clone(), cfg.status_addr, None) else {
+        bail!("the status API is off; set status_addr (or pass --status-addr) to the daemon's address");
+    };
+    let document = status::query(&addr)?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&document)?);
+    } else {
+        print!("{}", status::summary(&document, &addr));
+    }
+    Ok(())
+}
+
+fn run_once(args: &RuntimeArgs, state: &mut State) -> Result<()> {
+    let opts = render::RenderOptions {
+        dither: args.dither,
+        now: chrono::Local::now(),
+        stale_after_minutes: args.stale_after,
+    };
+    let mut alert_img = None;
+    let mut budget_pages = Vec::new();
+    let started = Instant::now();
+    let mut fetched = stats::fetch_stats(args.source.as_ref());
+    if let Ok(stats::Stats::Active(data)) = &mut fetched {
+        if let Some(hours) = &args.working_hours {
+            schedule::apply(hours, data, chrono::Utc::now());
+        }
+        apply_pace_model(args, state, data);
+        budget::apply(data, args.working_hours.as_ref(), chrono::Utc::now());
+    }
+    let source = args.source.describe();
+    debug!(
+        source = source.as_str(),
+        fetch_ms = started.elapsed().as_millis() as u64;
+        "Fetched stats"
+    );
+    {
+        let mut telemetry = state.telemetry.lock().unwrap();
+        telemetry.last_fetch = Some(telemetry::FetchRecord {
+            at: chrono::Utc::now(),
+            source,
+            outcome: match &fetched {
+                Ok(stats::Stats::Active(_)) => Ok("active".to_string()),
+                Ok(stats::Stats::Inactive { status, .. }) => Ok(status.to_string()),
+                Err(e) => Err(format!("{e:#}")),
+            },
+            message: match &fetched {
+                Ok(stats::Stats::Inactive { message, .. }) => message.clone(),
+                _ => None,
+            },
+        });
+        telemetry.usage = match &fetched {
+            Ok(stats::Stats::Active(data)) => Some(data.as_ref().clone()),
+            _ => None,
+        };
+    }
+    let fetched = fetched.unwrap_or_else(|e| {
+        warn!("Fetch failed: {e:#}");
+        stats::Stats::from_error(&e)
+    });
+    let started = Instant::now();
+    let (stats_pages, status_note) = match fetched {
+        stats::Stats::Active(data) => {
+            // Only runs that reach the device fire hooks; a one-shot look at
+            // the screen shouldn't notify anyone again.
+            if !args.dry_run && args.preview.is_none() && args.output.is_none() {
+                check_alerts(args, state, &data);
+            }
+            state.last_good = Some(
+                data.updated_at
+                    .clone()
+                    .unwrap_or_else(|| chrono::Utc::now().to_rfc3339()),
+            );
+            if args.alert_page && args.daemon.is_some() {
+                alert_img = render::render_alert(&data)?;
+            }
+            if args.with_budget {
+                budget_pages = render::render_budget(&data, &opts)?;
+            }
+            (render::render_bars(&data, &opts)?, String::new())
+        }
+        stats::Stats::Inactive { status, message } => {
+            let img = render::render_status(
+                &status,
+                message.as_deref(),
+                state.last_good.as_deref(),
+                &opts,
+            )?;
+            (vec![img], format!(" ({})", status))
+        }
+    };
+    debug!(page = "stats", render_ms = started.elapsed().as_millis() as u64; "Rendered stats");
+    {
+        let mut telemetry = state.telemetry.lock().unwrap();
+        telemetry
+            .frames
+            .retain(|page, _| !page.starts_with("stats-"));
+        for (i, img) in stats_pages.iter().enumerate() {
+            telemetry.frames.insert(page_name("stats", i), img.clone());
+        }
+        if !budget_pages.is_empty() {
+            telemetry
+                .frames
+                .retain(|page, _| !page.starts_with("budget"));
+        }
+        for (i, img) in budget_pages.iter().enumerate() {
+            telemetry.frames.insert(page_name("budget", i), img.clone());
+        }
+        if let Some(alert_img) = &alert_img {
+            telemetry
+                .frames
+                .insert("alert".to_string(), alert_img.clone());
+        }
+    }
+
+    // `--output` only saves the stats pages.
+    let all_pages = args.output.is_none();
+    let disk_pages = if args.with_disk && all_pages {
+        render_page(
+            &state.telemetry,
+            "disk",
+            disk_render::get_disk_info(),
+            |telemetry| &mut telemetry.disk,
+            |info| Ok(vec![disk_render::render_disk(info, args.dither)?]),
+        )?
+    } else {
+        Vec::new()
+    };
+
+    let system_pages = if args.with_system && all_pages {
+        let reading = state.system.sample();
+        render_page(
+            &state.telemetry,
+            "system",
+            reading,
+            |telemetry| &mut telemetry.system,
+            |info| {
+                let history = state.system.history();
+                Ok(vec![system_render::render_system(
+                    info,
+                    history,
+                    args.dither,
+                )?])
+            },
+        )?
+    } else {
+        Vec::new()
+    };
+
+    let network_pages = if args.with_network && all_pages {
+        // Fails e.g. when a configured interface is gone; it may come back.
+        let reading = state.network.sample(args.network_interfaces.as_deref());
+        render_page(
+            &state.telemetry,
+            "network",
+            reading,
+            |telemetry| &mut telemetry.network,
+            |info| network_render::render_network(info, state.network.history(), args.dither),
+        )?
+    } else {
+        Vec::new()
+    };
+
+    let sensors_pages = if args.with_sensors && all_pages {
+        render_page(
+            &state.telemetry,
+            "sensors",
+            Ok(sensors_render::get_sensor_info(args.sensors.as_deref())),
+            |telemetry| &mut telemetry.sensors,
+            |info| {
+                let img = sensors_render::render_sensors(
+                    info,
+                    args.temp_warn,
+                    args.temp_critical,
+                    args.dither,
+                )?;
+                Ok(vec![img])
+            },
+        )?
+    } else {
+        Vec::new()
+    };
+
+    // Album pages in slideshow order.
+    let stats_count = stats_pages.len();
+    let album: Vec<(String, RgbaImage)> = stats_pages
+        .into_iter()
+        .enumerate()
+        .map(|(i, img)| (page_name("stats", i), img))
+        .chain(
+            [
+                ("budget", budget_pages),
+                ("disk", disk_pages),
+                ("system", system_pages),
+                ("network", network_pages),
+                ("sensors", sensors_pages),
+            ]
+            .into_iter()
+            .flat_map(|(page, pages)| {
+                pages
+                    .into_iter()
+                    .enumerate()
+                    .map(move |(i, img)| (page_name(page, i), img))
+            }),
+        )
+        .collect();
+
+    // Telemetry keeps the plain frames; the device does its own encoding.
+    let (album, alert_img) =
+        if args.simulate_device && (args.output.is_some() || args.preview.is_some()) {
+            let album = album
+                .into_iter()
+                .map(|(page, img)| Ok((page, device::simulate(&img)?)))
+                .collect::<Result<Vec<_>>>()?;
+            (
+                album,
+                alert_img.map(|img| device::simulate(&img)).transpose()?,
+            )
+        } else {
+            (album, alert_img)
+        };
 
     if let Some(path) = &args.output {
-        stats_img.save(path)?;
-        println!("Saved to {path}");
+        for (i, (_, img)) in album.iter().take(stats_count).enumerate() {
+            let path = page_path(path, i);
+            img.save(&path)?;
+            info!("Saved to {}", path.display());
+        }
         return Ok(());
     }
 
-    if args.with_disk {
-        let disk_info = disk_render::get_disk_info()?;
-        let disk_img = disk_render::render_disk(&disk_info)?;
+    if let Some(mode) = args.preview {
+        if args.daemon.is_some() {
+            preview::clear_screen()?;
+        }
+        if let Some(alert_img) = &alert_img {
+            preview::show(alert_img, mode)?;
+            return Ok(());
+        }
+        for (_, img) in &album {
+            preview::show(img, mode)?;
+        }
+        return Ok(());
+    }
+    if args.dry_run {
+        return Ok(());
+    }
 
-        geekmagic_common::upload::upload_album(
-            &args.host,
-            &[("stats.jpg", &stats_img), ("disk.jpg", &disk_img)],
-        )?;
-        let now = chrono::Local::now().format("%H:%M:%S");
-        println!("[{now}] Pushed stats + disk to {}", args.host);
+    let started = Instant::now();
+    let pushed = push_to_device(args, state, &album, alert_img.as_ref(), &status_note);
+    let mut telemetry = state.telemetry.lock().unwrap();
+    telemetry.device_up = Some(pushed.is_ok());
+    if let Ok(bytes) = pushed {
+        telemetry.upload_seconds = Some(started.elapsed().as_secs_f64());
+        telemetry.upload_bytes = Some(bytes);
+        let pages: Vec<String> = match &alert_img {
+            Some(_) => vec!["alert".to_string()],
+            None => album.iter().map(|(page, _)| page.clone()).collect(),
+        };
+        let now = chrono::Utc::now();
+        for page in &pages {
+            telemetry.uploads.insert(page.clone(), now);
+        }
+        telemetry.upload_pages = pages;
+    }
+    pushed.map(|_| ())
+}
+
+/// Renders one of the optional pages from its `reading`, records both in
+/// telemetry and returns the pages to show. A failed reading only skips the
+/// page, so it doesn't hold back the others.
+fn render_page<T>(
+    telemetry: &telemetry::Shared,
+    page: &str,
+    reading: Result<T>,
+    slot: impl FnOnce(&mut telemetry::Telemetry) -> &mut Option<T>,
+    render: impl FnOnce(&T) -> Result<Vec<RgbaImage>>,
+) -> Result<Vec<RgbaImage>> {
+    let is_page = |name: &str| name == page || name.starts_with(&format!("{page}-"));
+    let info = match reading {
+        Ok(info) => info,
+        Err(e) => {
+            warn!(page; "Skipping page: {e:#}");
+            let mut telemetry = telemetry.lock().unwrap();
+            *slot(&mut telemetry) = None;
+            telemetry.frames.retain(|name, _| !is_page(name));
+            return Ok(Vec::new());
+        }
+    };
+    let started = Instant::now();
+    let pages = render(&info)?;
+    debug!(page, render_ms = started.elapsed().as_millis() as u64; "Rendered {page}");
+    let mut telemetry = telemetry.lock().unwrap();
+    *slot(&mut telemetry) = Some(info);
+    telemetry.frames.retain(|name, _| !is_page(name));
+    for (i, img) in pages.iter().enumerate() {
+        telemetry.frames.insert(page_name(page, i), img.clone());
+    }
+    Ok(pages)
+}
+
+/// Album page name of the `i`th page of a screen that can span several:
+/// `stats`, then `stats-2`, ...
+fn page_name(page: &str, i: usize) -> String {
+    match i {
+        0 => page.to_string(),
+        _ => format!("{page}-{}", i + 1),
+    }
+}
+
+/// Where `--output` saves the `i`th stats page: the path itself, then
+/// `name-2.png` and so on next to it.
+fn page_path(path: &str, i: usize) -> PathBuf {
+    let path = Path::new(path);
+    if i == 0 {
+        return path.to_path_buf();
+    }
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let name = match path.extension() {
+        Some(ext) => format!("{stem}-{}.{}", i + 1, ext.to_string_lossy()),
+        None => format!("{stem}-{}", i + 1),
+    };
+    path.with_file_name(name)
+}
+
+/// Uploads the album pages, or the alert page while one is active.
+/// Returns the number of JPEG bytes sent.
+fn push_to_device(
+    args: &RuntimeArgs,
+    state: &mut State,
+    album: &[(String, RgbaImage)],
+    alert_img: Option<&RgbaImage>,
+    status_note: &str,
+) -> Result<usize> {
+    let started = Instant::now();
+    let bytes = if let Some(alert_img) = alert_img {
+        if state.preempted.is_none() {
+            let settings = upload::album_settings(&args.host).unwrap_or_else(|e| {
+                warn!("Couldn't read album settings, will restore defaults: {e:#}");
+                AlbumSettings::default()
+            });
+            state.preempted = Some(settings);
+        }
+        let bytes = upload::show_alert(&args.host, alert_img)?;
+        info!(
+            bytes,
+            upload_ms = started.elapsed().as_millis() as u64;
+            "Pushed alert page to {}", args.host
+        );
+        bytes
     } else {
-        geekmagic_common::upload::upload_and_display(&args.host, &stats_img)?;
-        let now = chrono::Local::now().format("%H:%M:%S");
-        println!("[{now}] Pushed to {}", args.host);
+        let bytes = if let [(_, stats_img)] = album {
+            let bytes = upload::upload_and_display(&args.host, stats_img)?;
+            info!(
+                bytes,
+                upload_ms = started.elapsed().as_millis() as u64;
+                "Pushed{status_note} to {}", args.host
+            );
+            bytes
+        } else {
+            let files: Vec<(String, &RgbaImage)> = album
+                .iter()
+                .map(|(page, img)| (format!("{page}.jpg"), img))
+                .collect();
+            let files: Vec<(&str, &RgbaImage)> = files
+                .iter()
+                .map(|(file, img)| (file.as_str(), *img))
+                .collect();
+            let bytes = upload::upload_album(&args.host, &files)?;
+            let pages: Vec<&str> = album.iter().map(|(page, _)| page.as_str()).collect();
+            info!(
+                bytes,
+                upload_ms = started.elapsed().as_millis() as u64;
+                "Pushed {}{status_note} to {}", pages.join(" + "), args.host
+            );
+            bytes
+        };
+        if let Some(settings) = state.preempted {
+            upload::restore_album(&args.host, settings)?;
+            state.preempted = None;
+            info!("Alert cleared, restored the album on {}", args.host);
+        }
+        bytes
+    };
+
+    // Sent once, and again after a reload changes it or the host. A failure
+    // doesn't fail the push; it is retried on the next one.
+    if let Some(brightness) = args.brightness {
+        let wanted = (args.host.clone(), brightness);
+        if state.brightness_sent.as_ref() != Some(&wanted) {
+            match upload::set_brightness(&args.host, brightness) {
+                Ok(()) => state.brightness_sent = Some(wanted),
+                Err(e) => warn!("{e:#}"),
+            }
+        }
     }
 
-    Ok(())
+    Ok(bytes)
+}
+
+/// Applies a changed config file, keeping the previous settings if the new
+/// file fails to parse or no longer resolves to a usable configuration.
+fn reload_config(cli: &Args, watcher: &mut ConfigWatcher, args: &mut RuntimeArgs) -> bool {
+    let Some(loaded) = watcher.poll() else {
+        return false;
+    };
+    match loaded.and_then(|cfg| resolve_args(cli, cfg)) {
+        Ok(mut new_args) => {
+            // Daemon mode can't be switched off by editing the file.
+            new_args.daemon = new_args.daemon.or(args.daemon);
+            *args = new_args;
+            logging::init(args.log_level, args.log_format);
+            info!(
+                "Reloaded config: pushing every {}s to {}",
+                daemon_interval(args),
+                target(args)
+            );
+            true
+        }
+        Err(e) => {
+            error!("Config error, keeping previous settings: {e:#}");
+            false
+        }
+    }
+}
+
+fn daemon_interval(args: &RuntimeArgs) -> u64 {
+    // Previews don't touch the device, so they may refresh faster.
+    let min = if args.preview.is_some() { 1 } else { 10 };
+    args.daemon.unwrap_or(0).max(min)
+}
+
+fn target(args: &RuntimeArgs) -> String {
+    match args.preview {
+        Some(mode) => format!("{} terminal preview", mode.resolve()),
+        None => args.host.clone(),
+    }
 }
 
 fn main() -> Result<()> {
-    let args = resolve_args(Args::parse())?;
+    let cli = Args::parse();
+    match &cli.command {
+        Some(Command::Config { action }) => {
+            return match action {
+                ConfigAction::Check => config_check(&cli),
+                ConfigAction::Init { force } => config_init(&cli, *force),
+            };
+        }
+        Some(Command::Status { json }) => return daemon_status(&cli, *json),
+        None => {}
+    }
+    let loaded = config::load_layered(cli.config.as_deref(), cli.profile.as_deref())?;
+    let mut args = resolve_args(&cli, loaded.config.clone())?;
+    logging::init(args.log_level, args.log_format);
+    if args.json {
+        logging::stderr_only();
+    }
+    for warning in loaded.warnings() {
+        warn!("{warning}");
+    }
 
-    if let Some(interval) = args.daemon {
-        let interval = interval.max(10);
-        println!("Daemon mode: pushing every {interval}s to {}", args.host);
+    if args.daemon.is_some() {
+        let mut watcher = ConfigWatcher::new(cli.config.as_deref(), cli.profile.as_deref());
+        info!(
+            "Daemon mode: pushing every {}s to {}",
+            daemon_interval(&args),
+            target(&args)
+        );
+        let mut state = State::default();
+        state.telemetry.lock().unwrap().started_at = Some(chrono::Utc::now());
+        // Another daemon (e.g. a second profile) may hold either port; the
+        // display keeps updating either way.
+        if let Some(addr) = &args.metrics {
+            match metrics::serve(addr, state.telemetry.clone()) {
+                Ok(bound) => info!("Serving metrics on http://{bound}/metrics"),
+                Err(e) => warn!("Metrics disabled: {e:#}"),
+            }
+        }
+        if let Some(addr) = &args.status_addr {
+            match status::serve(addr, state.telemetry.clone()) {
+                Ok(bound) => info!("Status API on http://{bound}/status"),
+                Err(e) => warn!("Status API disabled: {e:#}"),
+            }
+        }
         loop {
-            if let Err(e) = run_once(&args) {
-                let now = chrono::Local::now().format("%H:%M:%S");
-                eprintln!("[{now}] Error: {e}");
+            let result = run_once(&args, &mut state);
+            let mut telemetry = state.telemetry.lock().unwrap();
+            match result {
+                Ok(()) => telemetry.record_success(),
+                Err(e) => {
+                    error!("{e:#}");
+                    telemetry.record_error(format!("{e:#}"));
+                }
             }
-            thread::sleep(Duration::from_secs(interval));
+            telemetry.target = target(&args);
+            telemetry.interval_seconds = daemon_interval(&args);
+            telemetry.next_run =
+                Some(chrono::Utc::now() + chrono::Duration::seconds(daemon_interval(&args) as i64));
+            drop(telemetry);
+            // Sleep in short steps so config edits apply without waiting a full interval.
+            let mut waited = 0;
+            while waited < daemon_interval(&args) {
+                thread::sleep(Duration::from_secs(1));
+                waited += 1;
+                if reload_config(&cli, &mut watcher, &mut args) {
+                    break;
+                }
+            }
+        }
+    } else if args.json {
+        let mut state = State::default();
+        let result = run_once(&args, &mut state);
+        let report = report(&args, &state, &result);
+        report.print()?;
+        result?;
+        // A failed fetch still shows a status screen, but the run failed.
+        match report.error {
+            Some(error) => Err(anyhow!(error)),
+            None => Ok(()),
         }
     } else {
-        run_once(&args)
+        run_once(&args, &mut State::default())
+    }
+}
+
+/// The `--json` report of a one-shot run, read back from what `run_once`
+/// recorded.
+fn report(args: &RuntimeArgs, state: &State, result: &Result<()>) -> Report {
+    let telemetry = state.telemetry.lock().unwrap();
+    let fetch = telemetry.last_fetch.as_ref();
+    Report {
+        status: fetch.and_then(|f| f.outcome.clone().ok()),
+        message: fetch.and_then(|f| f.message.clone()),
+        usage: telemetry.usage.clone(),
+        disk: telemetry.disk.clone(),
+        system: telemetry.system.clone(),
+        network: telemetry.network.clone(),
+        sensors: telemetry.sensors.clone(),
+        upload: telemetry.upload_bytes.map(|bytes| UploadResult {
+            host: args.host.clone(),
+            pages: telemetry.upload_pages.clone(),
+            bytes,
+            seconds: telemetry.upload_seconds.unwrap_or_default(),
+        }),
+        error: match result {
+            Err(e) => Some(format!("{e:#}")),
+            Ok(()) => fetch.and_then(|f| f.outcome.clone().err()),
+        },
     }
 }
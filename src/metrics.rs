@@ -0,0 +1,293 @@
+use std::fmt::Write;
+
+use anyhow::Result;
+
+use crate::http::{self, Response};
+use crate::network_render::{InterfaceInfo, NetworkInfo};
+use crate::sensors_render::{SensorInfo, SensorKind};
+use crate::stats::UsageWindow;
+use crate::telemetry::{Shared, Telemetry};
+
+struct Family<'a> {
+    out: &'a mut String,
+}
+
+impl Family<'_> {
+    fn start<'a>(out: &'a mut String, name: &str, kind: &str, help: &str) -> Family<'a> {
+        let _ = writeln!(out, "# HELP {name} {help}");
+        let _ = writeln!(out, "# TYPE {name} {kind}");
+        Family { out }
+    }
+
+    fn sample(&mut self, name: &str, labels: &str, value: f64) {
+        let _ = if labels.is_empty() {
+            writeln!(self.out, "{name} {value}")
+        } else {
+            writeln!(self.out, "{name}{{{labels}}} {value}")
+        };
+    }
+}
+
+fn gauge(out: &mut String, name: &str, help: &str, value: Option<f64>) {
+    if let Some(value) = value {
+        Family::start(out, name, "gauge", help).sample(name, "", value);
+    }
+}
+
+/// One gauge with a `window` label per usage window that has a value.
+fn window_gauge(
+    out: &mut String,
+    name: &str,
+    help: &str,
+    windows: &[(String, &UsageWindow)],
+    value: impl Fn(&UsageWindow) -> Option<f64>,
+) {
+    let values: Vec<_> = windows
+        .iter()
+        .filter_map(|(label, w)| value(w).map(|v| (label, v)))
+        .collect();
+    if values.is_empty() {
+        return;
+    }
+    let mut family = Family::start(out, name, "gauge", help);
+    for (label, v) in values {
+        family.sample(name, &format!("window=\"{label}\""), v);
+    }
+}
+
+/// One gauge with an `interface` label per shown network interface.
+fn interface_gauge(
+    out: &mut String,
+    name: &str,
+    help: &str,
+    network: &NetworkInfo,
+    value: impl Fn(&InterfaceInfo) -> f64,
+) {
+    if network.interfaces.is_empty() {
+        return;
+    }
+    let mut family = Family::start(out, name, "gauge", help);
+    for iface in &network.interfaces {
+        family.sample(name, &format!("interface=\"{}\"", iface.name), value(iface));
+    }
+}
+
+/// Escapes a label value for the exposition format.
+fn escape_label(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// One gauge with a `sensor` label per shown sensor of `kind`.
+fn sensor_gauge(out: &mut String, name: &str, help: &str, info: &SensorInfo, kind: SensorKind) {
+    let mut sensors = info.sensors.iter().filter(|s| s.kind == kind).peekable();
+    if sensors.peek().is_none() {
+        return;
+    }
+    let mut family = Family::start(out, name, "gauge", help);
+    for sensor in sensors {
+        family.sample(
+            name,
+            &format!("sensor=\"{}\"", escape_label(&sensor.id)),
+            sensor.value,
+        );
+    }
+}
+
+/// Encodes the daemon's telemetry in the Prometheus text exposition format. Metrics
+/// without a value yet are left out rather than reported as zero.
+pub fn encode(s: &Telemetry) -> String {
+    let mut out = String::new();
+
+    let fetched = s.last_fetch.as_ref().map(|f| f.outcome.is_ok());
+    gauge(
+        &mut out,
+        "geekmagic_usage_up",
+        "1 if the last fetch returned usage data.",
+        fetched.map(|ok| f64::from(u8::from(ok && s.usage.is_some()))),
+    );
+    // Never export usage from before a failed fetch.
+    if let Some(usage) = s.usage.as_ref().filter(|_| fetched != Some(false)) {
+        // Labelled `session`, `weekly`, `weekly_opus`, ...
+        let windows: Vec<(String, &UsageWindow)> = usage
+            .windows()
+            .into_iter()
+            .map(|(label, w)| {
+                let label = label.to_lowercase().replace(' ', "_");
+                (escape_label(&label), w)
+            })
+            .collect();
+        window_gauge(
+            &mut out,
+            "geekmagic_usage_utilization_percent",
+            "Utilization of the usage window.",
+            &windows,
+            |w| Some(w.utilization),
+        );
+        window_gauge(
+            &mut out,
+            "geekmagic_usage_expected_percent",
+            "Utilization expected at this point of the window at an even pace.",
+            &windows,
+            |w| w.pace.as_ref().map(|p| p.expected_percent),
+        );
+        window_gauge(
+            &mut out,
+            "geekmagic_usage_pace_delta_percent",
+            "Utilization minus expected; positive means ahead of pace.",
+            &windows,
+            |w| w.pace.as_ref().map(|p| p.delta_percent),
+        );
+        window_gauge(
+            &mut out,
+            "geekmagic_usage_will_last_to_reset",
+            "1 if usage at the current pace lasts until the window resets.",
+            &windows,
+            |w| {
+                w.pace
+                    .as_ref()
+                    .map(|p| f64::from(u8::from(p.will_last_to_reset)))
+            },
+        );
+        window_gauge(
+            &mut out,
+            "geekmagic_usage_resets_in_seconds",
+            "Time until the usage window resets.",
+            &windows,
+            |w| w.resets_in_minutes.map(|m| m * 60.0),
+        );
+    }
+
+    if let Some(disk) = &s.disk {
+        gauge(
+            &mut out,
+            "geekmagic_disk_total_bytes",
+            "Size of the root volume.",
+            Some(disk.total_bytes as f64),
+        );
+        gauge(
+            &mut out,
+            "geekmagic_disk_used_bytes",
+            "Used space on the root volume.",
+            Some(disk.used_bytes as f64),
+        );
+        gauge(
+            &mut out,
+            "geekmagic_disk_free_bytes",
+            "Free space on the root volume.",
+            Some(disk.free_bytes as f64),
+        );
+    }
+
+    if let Some(system) = &s.system {
+        gauge(
+            &mut out,
+            "geekmagic_system_cpu_percent",
+            "Busy share of all CPUs since the previous reading.",
+            Some(system.cpu_percent),
+        );
+        gauge(
+            &mut out,
+            "geekmagic_system_memory_total_bytes",
+            "Physical memory.",
+            Some(system.memory_total_bytes as f64),
+        );
+        gauge(
+            &mut out,
+            "geekmagic_system_memory_used_bytes",
+            "Memory not available to new processes.",
+            Some(system.memory_used_bytes as f64),
+        );
+        gauge(
+            &mut out,
+            "geekmagic_system_load1",
+            "One-minute load average.",
+            Some(system.load[0]),
+        );
+    }
+
+    if let Some(network) = &s.network {
+        interface_gauge(
+            &mut out,
+            "geekmagic_network_receive_bytes_per_second",
+            "Receive rate since the previous reading.",
+            network,
+            |i| i.rx_rate,
+        );
+        interface_gauge(
+            &mut out,
+            "geekmagic_network_transmit_bytes_per_second",
+            "Transmit rate since the previous reading.",
+            network,
+            |i| i.tx_rate,
+        );
+    }
+
+    if let Some(sensors) = &s.sensors {
+        sensor_gauge(
+            &mut out,
+            "geekmagic_sensor_temperature_celsius",
+            "Temperature of the sensor.",
+            sensors,
+            SensorKind::Temperature,
+        );
+        sensor_gauge(
+            &mut out,
+            "geekmagic_sensor_fan_rpm",
+            "Speed of the fan.",
+            sensors,
+            SensorKind::Fan,
+        );
+    }
+
+    gauge(
+        &mut out,
+        "geekmagic_device_up",
+        "1 if the last push to the display succeeded.",
+        s.device_up.map(|up| f64::from(u8::from(up))),
+    );
+    gauge(
+        &mut out,
+        "geekmagic_upload_duration_seconds",
+        "Duration of the last successful push.",
+        s.upload_seconds,
+    );
+    gauge(
+        &mut out,
+        "geekmagic_upload_bytes",
+        "JPEG bytes sent in the last successful push.",
+        s.upload_bytes.map(|b| b as f64),
+    );
+    gauge(
+        &mut out,
+        "geekmagic_last_success_timestamp_seconds",
+        "Unix time of the last successful update.",
+        s.last_success.map(|t| t as f64),
+    );
+    Family::start(
+        &mut out,
+        "geekmagic_updates_total",
+        "counter",
+        "Successful updates.",
+    )
+    .sample("geekmagic_updates_total", "", s.updates_total as f64);
+    Family::start(
+        &mut out,
+        "geekmagic_errors_total",
+        "counter",
+        "Updates that failed.",
+    )
+    .sample("geekmagic_errors_total", "", s.errors_total as f64);
+
+    out
+}
+
+/// Serves `/metrics` on `addr` from a background thread.
+pub fn serve(addr: &str, shared: Shared) -> Result<std::net::SocketAddr> {
+    http::serve(addr, move |path| match path {
+        "/metrics" => {
+            let body = encode(&shared.lock().unwrap());
+            Response::ok("text/plain; version=0.0.4; charset=utf-8", body)
+        }
+        _ => Response::not_found(),
+    })
+}
@@ -0,0 +1,442 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use ab_glyph::{FontRef, PxScale};
+use anyhow::{bail, Context, Result};
+use image::{Rgba, RgbaImage};
+use imageproc::drawing::draw_text_mut;
+use serde::Serialize;
+
+use crate::disk_render::format_size;
+use crate::render::{
+    draw_gradient_bar, draw_rounded_rect, draw_text_right, HistoryGraph, BG, FONT_BOLD_BYTES,
+    FONT_BYTES, H, PANEL_BG, SEPARATOR, TEXT_DIM, TEXT_MUTED, TEXT_PRIMARY, W,
+};
+use crate::system_render::HISTORY_LEN;
+
+const RX_LEFT: Rgba<u8> = Rgba([59, 130, 246, 255]);
+const RX_RIGHT: Rgba<u8> = Rgba([6, 182, 212, 255]);
+const TX_LEFT: Rgba<u8> = Rgba([99, 102, 241, 255]);
+const TX_RIGHT: Rgba<u8> = Rgba([139, 92, 246, 255]);
+
+/// Interfaces shown when the `network_interfaces` setting is unset: the
+/// busiest ones since boot.
+const DEFAULT_INTERFACES: usize = 2;
+
+/// Interfaces per network page.
+const INTERFACES_PER_PAGE: usize = 2;
+
+/// How long the first reading waits for a second one, so a one-shot run
+/// shows current rates.
+const FIRST_RATE_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Rate bars are full at the busiest reading in the history, but never for
+/// less than this (1 Mbit/s), so an idle link doesn't show full bars.
+const MIN_SCALE_BYTES_PER_SECOND: f64 = 125_000.0;
+
+/// Received and sent bytes since boot, per interface.
+pub type Counters = BTreeMap<String, (u64, u64)>;
+
+/// Traffic of one interface.
+#[derive(Debug, Clone, Serialize)]
+pub struct InterfaceInfo {
+    pub name: String,
+    /// Received since boot.
+    pub rx_bytes: u64,
+    /// Sent since boot.
+    pub tx_bytes: u64,
+    /// Bytes per second since the previous reading.
+    pub rx_rate: f64,
+    pub tx_rate: f64,
+}
+
+/// One reading of the shown interfaces, in display order.
+#[derive(Debug, Clone, Serialize)]
+pub struct NetworkInfo {
+    pub interfaces: Vec<InterfaceInfo>,
+}
+
+/// Takes readings of the interface counters, turns them into rates across
+/// ticks and keeps the last [`HISTORY_LEN`] readings. Reads `/proc/net/dev`
+/// on Linux and `netstat -ib` on macOS.
+#[derive(Debug, Default)]
+pub struct NetworkMonitor {
+    last: Option<(Instant, Counters)>,
+    history: Vec<NetworkInfo>,
+}
+
+impl NetworkMonitor {
+    pub fn new() -> NetworkMonitor {
+        NetworkMonitor::default()
+    }
+
+    /// Takes a reading of `interfaces` (in that order), or of the busiest
+    /// non-loopback interfaces when `None`, and adds it to the history.
+    pub fn sample(&mut self, interfaces: Option<&[String]>) -> Result<NetworkInfo> {
+        let mut counters = read_counters()?;
+        let mut now = Instant::now();
+        let (then, previous) = match self.last.take() {
+            Some(last) => last,
+            None => {
+                thread::sleep(FIRST_RATE_INTERVAL);
+                let first = (now, counters);
+                counters = read_counters()?;
+                now = Instant::now();
+                first
+            }
+        };
+        let seconds = now.duration_since(then).as_secs_f64().max(f64::EPSILON);
+
+        let names: Vec<String> = match interfaces {
+            Some(names) => {
+                let found: Vec<String> = names
+                    .iter()
+                    .filter(|name| counters.contains_key(name.as_str()))
+                    .cloned()
+                    .collect();
+                if found.is_empty() {
+                    // Keep these counters, so the next sample has a baseline
+                    // if the interface comes back.
+                    self.last = Some((now, counters.clone()));
+                    let available: Vec<&str> = counters.keys().map(String::as_str).collect();
+                    bail!(
+                        "none of the network interfaces {} exist (available: {})",
+                        names.join(", "),
+                        available.join(", ")
+                    );
+                }
+                found
+            }
+            None => {
+                let mut busiest: Vec<(&String, u64)> = counters
+                    .iter()
+                    .filter(|(name, _)| !matches!(name.as_str(), "lo" | "lo0"))
+                    .map(|(name, (rx, tx))| (name, rx + tx))
+                    .filter(|(_, total)| *total > 0)
+                    .collect();
+                busiest.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+                busiest
+                    .into_iter()
+                    .take(DEFAULT_INTERFACES)
+                    .map(|(name, _)| name.clone())
+                    .collect()
+            }
+        };
+
+        let rate = |now: u64, before: Option<u64>| {
+            // A counter that went backwards was reset (or the interface
+            // came back); count it as idle for this tick.
+            before.map_or(0.0, |before| now.saturating_sub(before) as f64 / seconds)
+        };
+        let info = NetworkInfo {
+            interfaces: names
+                .into_iter()
+                .map(|name| {
+                    let (rx_bytes, tx_bytes) = counters[&name];
+                    let before = previous.get(&name);
+                    InterfaceInfo {
+                        rx_rate: rate(rx_bytes, before.map(|b| b.0)),
+                        tx_rate: rate(tx_bytes, before.map(|b| b.1)),
+                        name,
+                        rx_bytes,
+                        tx_bytes,
+                    }
+                })
+                .collect(),
+        };
+
+        self.last = Some((now, counters));
+        self.history.push(info.clone());
+        let excess = self.history.len().saturating_sub(HISTORY_LEN);
+        self.history.drain(..excess);
+        Ok(info)
+    }
+
+    /// Readings oldest first, ending with the latest.
+    pub fn history(&self) -> &[NetworkInfo] {
+        &self.history
+    }
+}
+
+fn read_counters() -> Result<Counters> {
+    if Path::new("/proc/net/dev").exists() {
+        let text = fs::read_to_string("/proc/net/dev").context("failed to read /proc/net/dev")?;
+        parse_proc_net_dev(&text)
+    } else {
+        let output = Command::new("netstat")
+            .arg("-ib")
+            .output()
+            .context("failed to run netstat")?;
+        parse_netstat(&String::from_utf8_lossy(&output.stdout))
+    }
+}
+
+/// `/proc/net/dev`: two header lines, then `name: rx_bytes ... tx_bytes ...`
+/// with the transmit columns starting at the ninth field.
+pub fn parse_proc_net_dev(text: &str) -> Result<Counters> {
+    text.lines()
+        .skip(2)
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, fields)| {
+            let fields: Vec<&str> = fields.split_whitespace().collect();
+            let field = |i: usize| -> Result<u64> {
+                fields
+                    .get(i)
+                    .and_then(|v| v.parse().ok())
+                    .with_context(|| format!("malformed /proc/net/dev line for {}", name.trim()))
+            };
+            Ok((name.trim().to_string(), (field(0)?, field(8)?)))
+        })
+        .collect()
+}
+
+/// `netstat -ib`: one row per interface address; the `<Link#n>` row has the
+/// interface totals. The address column can be empty, so the byte columns
+/// are counted from the right (`... Ibytes Opkts Oerrs Obytes Coll`).
+pub fn parse_netstat(text: &str) -> Result<Counters> {
+    let mut counters = BTreeMap::new();
+    for line in text.lines().skip(1) {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.len() < 10 || !fields[2].starts_with("<Link#") {
+            continue;
+        }
+        let from_right = |i: usize| -> Result<u64> {
+            fields[fields.len() - i]
+                .parse()
+                .with_context(|| format!("malformed netstat line for {}", fields[0]))
+        };
+        counters
+            .entry(fields[0].trim_end_matches('*').to_string())
+            .or_insert((from_right(5)?, from_right(2)?));
+    }
+    Ok(counters)
+}
+
+/// "12.3 MB/s", in the same decimal units as disk sizes.
+pub fn format_rate(bytes_per_second: f64) -> String {
+    let b = bytes_per_second.max(0.0);
+    if b >= 1e9 {
+        format!("{:.1} GB/s", b / 1e9)
+    } else if b >= 1e6 {
+        format!("{:.1} MB/s", b / 1e6)
+    } else if b >= 1e3 {
+        format!("{:.0} KB/s", b / 1e3)
+    } else {
+        format!("{b:.0} B/s")
+    }
+}
+
+/// Received traffic as a filled area and sent traffic as a line, scaled to
+/// `max` and right-aligned like the system graphs.
+#[allow(clippy::too_many_arguments)]
+fn draw_sparkline(
+    img: &mut RgbaImage,
+    x: i32,
+    y: i32,
+    w: u32,
+    h: u32,
+    rx: &[f64],
+    tx: &[f64],
+    max: f64,
+    dither: bool,
+) {
+    let graph = HistoryGraph::new(img, x, y, w, h, HISTORY_LEN, max, dither);
+    graph.fill_area(img, rx, RX_RIGHT);
+    let inner_h = graph.inner_h();
+    let mut last_tx: Option<u32> = None;
+    for px in 0..graph.inner_w() {
+        if let Some(filled) = graph.height_at(tx, px) {
+            let top = (inner_h - filled.round() as u32).min(inner_h - 1);
+            // Join to the previous column so steep changes stay connected.
+            let (from, to) = match last_tx {
+                Some(prev) => (prev.min(top), prev.max(top)),
+                None => (top, top),
+            };
+            for py in from..=to {
+                graph.put(img, px, py, TX_RIGHT);
+            }
+            last_tx = Some(top);
+        }
+    }
+}
+
+/// Network pages: rx/tx rates with bars scaled to the busiest recent
+/// reading, totals since boot and a graph of `history` (oldest first,
+/// ending with `info`), two interfaces to a page.
+pub fn render_network(
+    info: &NetworkInfo,
+    history: &[NetworkInfo],
+    dither: bool,
+) -> Result<Vec<RgbaImage>> {
+    let font = FontRef::try_from_slice(FONT_BYTES)?;
+    let font_bold = FontRef::try_from_slice(FONT_BOLD_BYTES)?;
+
+    if info.interfaces.is_empty() {
+        let mut img = RgbaImage::from_pixel(W, H, BG);
+        draw_text_mut(
+            &mut img,
+            TEXT_DIM,
+            54,
+            110,
+            PxScale::from(16.0),
+            &font,
+            "No network traffic",
+        );
+        return Ok(vec![img]);
+    }
+
+    let pages = info.interfaces.len().div_ceil(INTERFACES_PER_PAGE);
+    Ok(info
+        .interfaces
+        .chunks(INTERFACES_PER_PAGE)
+        .enumerate()
+        .map(|(i, interfaces)| {
+            let page = (pages > 1).then(|| format!("{}/{pages}", i + 1));
+            render_network_page(
+                interfaces,
+                history,
+                page.as_deref(),
+                dither,
+                &font,
+                &font_bold,
+            )
+        })
+        .collect())
+}
+
+fn render_network_page(
+    interfaces: &[InterfaceInfo],
+    history: &[NetworkInfo],
+    page: Option<&str>,
+    dither: bool,
+    font: &FontRef,
+    font_bold: &FontRef,
+) -> RgbaImage {
+    let mut img = RgbaImage::from_pixel(W, H, BG);
+
+    let mx = 16i32;
+    let right_edge = W as i32 - mx;
+    let content_w = (right_edge - mx) as u32;
+
+    // Header
+    let header_y = 10;
+    draw_text_mut(
+        &mut img,
+        TEXT_PRIMARY,
+        mx,
+        header_y,
+        PxScale::from(17.0),
+        font_bold,
+        "Network",
+    );
+    if let Some(page) = page {
+        draw_text_right(
+            &mut img,
+            TEXT_DIM,
+            right_edge,
+            header_y + 1,
+            15.0,
+            font,
+            page,
+        );
+    }
+    draw_rounded_rect(&mut img, mx, 33, content_w, 1, 0, SEPARATOR);
+
+    let section_h = 98i32;
+    let gap = 1i32;
+    let start_y = 37;
+
+    for (i, iface) in interfaces.iter().enumerate() {
+        let by = start_y + (i as i32) * (section_h + gap);
+        let text_x = mx + 8;
+        let inner_right = right_edge - 6;
+
+        draw_rounded_rect(
+            &mut img,
+            mx - 4,
+            by - 2,
+            content_w + 8,
+            section_h as u32 + 4,
+            10,
+            PANEL_BG,
+        );
+
+        // Row 1: name, totals since boot
+        draw_text_mut(
+            &mut img,
+            TEXT_MUTED,
+            text_x,
+            by + 4,
+            PxScale::from(14.0),
+            font_bold,
+            &iface.name,
+        );
+        let totals = format!(
+            "↓ {}  ↑ {}",
+            format_size(iface.rx_bytes),
+            format_size(iface.tx_bytes)
+        );
+        draw_text_right(&mut img, TEXT_DIM, inner_right, by + 6, 12.0, font, &totals);
+
+        // Rows 2-3: rates with bars
+        let rates: Vec<(f64, f64)> = history
+            .iter()
+            .filter_map(|s| s.interfaces.iter().find(|other| other.name == iface.name))
+            .map(|other| (other.rx_rate, other.tx_rate))
+            .collect();
+        let peak = rates
+            .iter()
+            .map(|&(rx, tx)| rx.max(tx))
+            .fold(MIN_SCALE_BYTES_PER_SECOND, f64::max);
+        let bar_x = mx + 106;
+        let bar_w = (inner_right - bar_x) as u32;
+        for (row, arrow, rate, (left, right)) in [
+            (0, "↓", iface.rx_rate, (RX_LEFT, RX_RIGHT)),
+            (1, "↑", iface.tx_rate, (TX_LEFT, TX_RIGHT)),
+        ] {
+            let y = by + 24 + row * 21;
+            draw_text_mut(
+                &mut img,
+                TEXT_PRIMARY,
+                text_x,
+                y,
+                PxScale::from(17.0),
+                font_bold,
+                &format!("{arrow} {}", format_rate(rate)),
+            );
+            draw_gradient_bar(
+                &mut img,
+                bar_x,
+                y + 6,
+                bar_w,
+                8,
+                (rate / peak) as f32,
+                left,
+                right,
+                4,
+                dither,
+            );
+        }
+
+        // Row 4: history
+        let rx: Vec<f64> = rates.iter().map(|r| r.0).collect();
+        let tx: Vec<f64> = rates.iter().map(|r| r.1).collect();
+        draw_sparkline(
+            &mut img,
+            text_x,
+            by + 68,
+            (inner_right - text_x) as u32,
+            26,
+            &rx,
+            &tx,
+            peak,
+            dither,
+        );
+    }
+
+    img
+}
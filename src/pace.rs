@@ -0,0 +1,330 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Datelike, Local, NaiveDate, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::config;
+use crate::stats::ActiveData;
+
+/// Samples closer together than this are skipped, which keeps the history
+/// small when the daemon or a preview runs with a short interval.
+const SAMPLE_SPACING_SECONDS: i64 = 5 * 60;
+
+/// Samples older than this are dropped.
+const RETENTION_DAYS: i64 = 28;
+
+/// How far back the `recent` model looks.
+const RECENT_MINUTES: i64 = 120;
+
+/// Half-life of the `smoothed` model's moving average.
+const SMOOTHING_HALF_LIFE_MINUTES: f64 = 60.0;
+
+/// History the `weekday` model needs before it trusts its averages.
+const WEEKDAY_MIN_DAYS: i64 = 7;
+
+/// Step used to project usage forward.
+const PROJECTION_STEP_MINUTES: f64 = 5.0;
+
+/// How the time until a window runs out is estimated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PaceModel {
+    /// Average rate since the window started (the source's own model).
+    Linear,
+    /// Rate over the last two hours.
+    Recent,
+    /// Exponentially smoothed rate since the window started, weighted
+    /// towards the last hour or so.
+    Smoothed,
+    /// Average usage per weekday over past weeks, so weekends without coding
+    /// don't count as burn. Applies to the weekly window; the session window
+    /// uses the recent rate.
+    Weekday,
+}
+
+impl FromStr for PaceModel {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Ok(match s {
+            "linear" => PaceModel::Linear,
+            "recent" => PaceModel::Recent,
+            "smoothed" => PaceModel::Smoothed,
+            "weekday" => PaceModel::Weekday,
+            other => {
+                bail!("unknown pace model `{other}` (expected linear, recent, smoothed or weekday)")
+            }
+        })
+    }
+}
+
+impl fmt::Display for PaceModel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            PaceModel::Linear => "linear",
+            PaceModel::Recent => "recent",
+            PaceModel::Smoothed => "smoothed",
+            PaceModel::Weekday => "weekday",
+        })
+    }
+}
+
+/// One recorded utilization reading.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Sample {
+    /// Unix time.
+    pub at: i64,
+    pub utilization: f64,
+}
+
+impl Sample {
+    fn minutes_since(&self, earlier: &Sample) -> f64 {
+        (self.at - earlier.at) as f64 / 60.0
+    }
+}
+
+/// Utilization samples per usage window (`five_hour`, `seven_day`, ...),
+/// kept on disk so one-shot runs and restarts build on earlier readings.
+#[derive(Debug, Default)]
+pub struct History {
+    path: Option<PathBuf>,
+    windows: BTreeMap<String, Vec<Sample>>,
+}
+
+/// `history.json` in the per-user state directory.
+pub fn default_history_path() -> Option<PathBuf> {
+    config::user_state_dir().map(|dir| dir.join("history.json"))
+}
+
+impl History {
+    /// Reads the default history file; without a state directory the
+    /// history lives in memory only.
+    pub fn load() -> Result<History> {
+        match default_history_path() {
+            Some(path) => History::load_from(path),
+            None => Ok(History::default()),
+        }
+    }
+
+    /// Reads the history file at `path`, which [`History::save`] writes back
+    /// to; a missing file is an empty history.
+    pub fn load_from(path: PathBuf) -> Result<History> {
+        let windows = if path.exists() {
+            let text = fs::read_to_string(&path)
+                .with_context(|| format!("failed to read {}", path.display()))?;
+            serde_json::from_str(&text)
+                .with_context(|| format!("failed to parse {}", path.display()))?
+        } else {
+            BTreeMap::new()
+        };
+        Ok(History {
+            path: Some(path),
+            windows,
+        })
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)
+                .with_context(|| format!("failed to create {}", dir.display()))?;
+        }
+        fs::write(path, serde_json::to_string(&self.windows)?)
+            .with_context(|| format!("failed to write {}", path.display()))
+    }
+
+    /// Adds the current utilization of each window and drops old samples.
+    pub fn record(&mut self, data: &ActiveData, now: DateTime<Utc>) {
+        let now = now.timestamp();
+        for (key, window) in data.windows_by_key() {
+            let samples = self.windows.entry(key.to_string()).or_default();
+            samples.retain(|s| now - s.at < RETENTION_DAYS * 86_400);
+            if samples
+                .last()
+                .is_some_and(|last| now - last.at < SAMPLE_SPACING_SECONDS)
+            {
+                continue;
+            }
+            samples.push(Sample {
+                at: now,
+                utilization: window.utilization,
+            });
+        }
+    }
+
+    pub fn samples(&self, window: &str) -> &[Sample] {
+        self.windows.get(window).map_or(&[], Vec::as_slice)
+    }
+}
+
+/// Projects how fast a window's utilization grows from now on.
+pub trait PaceEstimator {
+    /// Expected burn in percentage points per minute at `at`.
+    fn rate_at(&self, at: DateTime<Utc>) -> f64;
+}
+
+/// The same rate at all times.
+struct ConstantRate(f64);
+
+impl PaceEstimator for ConstantRate {
+    fn rate_at(&self, _at: DateTime<Utc>) -> f64 {
+        self.0
+    }
+}
+
+/// A daily rate per weekday (local time), Monday first.
+struct WeekdayRate([f64; 7]);
+
+impl PaceEstimator for WeekdayRate {
+    fn rate_at(&self, at: DateTime<Utc>) -> f64 {
+        let weekday = at.with_timezone(&Local).weekday();
+        self.0[weekday.num_days_from_monday() as usize] / 1440.0
+    }
+}
+
+/// Samples since the last reset (the last drop in utilization).
+fn current_window(samples: &[Sample]) -> &[Sample] {
+    let start = samples
+        .windows(2)
+        .rposition(|pair| pair[1].utilization < pair[0].utilization)
+        .map_or(0, |i| i + 1);
+    &samples[start..]
+}
+
+fn recent_rate(samples: &[Sample], now: i64) -> Option<f64> {
+    let samples = current_window(samples);
+    let recent: Vec<_> = samples
+        .iter()
+        .filter(|s| now - s.at <= RECENT_MINUTES * 60)
+        .collect();
+    let (first, last) = (recent.first()?, recent.last()?);
+    let minutes = last.minutes_since(first);
+    // A couple of samples a few minutes apart say little about the rate.
+    if minutes < 15.0 {
+        return None;
+    }
+    Some(((last.utilization - first.utilization) / minutes).max(0.0))
+}
+
+fn smoothed_rate(samples: &[Sample]) -> Option<f64> {
+    let mut rate = None;
+    for pair in current_window(samples).windows(2) {
+        let minutes = pair[1].minutes_since(&pair[0]);
+        if minutes <= 0.0 {
+            continue;
+        }
+        let step = (pair[1].utilization - pair[0].utilization) / minutes;
+        let weight = 1.0 - 0.5_f64.powf(minutes / SMOOTHING_HALF_LIFE_MINUTES);
+        rate = Some(match rate {
+            None => step,
+            Some(r) => r + weight * (step - r),
+        });
+    }
+    rate.map(|r: f64| r.max(0.0))
+}
+
+fn weekday_rates(samples: &[Sample], now: i64) -> Option<[f64; 7]> {
+    let first = samples.first()?;
+    if now - first.at < WEEKDAY_MIN_DAYS * 86_400 {
+        return None;
+    }
+    let local_date =
+        |at: i64| -> Option<NaiveDate> { Some(Local.timestamp_opt(at, 0).single()?.date_naive()) };
+
+    let mut used = [0.0; 7];
+    for pair in samples.windows(2) {
+        // A drop is a reset: the usage before it was already counted, and
+        // the window has since climbed from zero.
+        let delta = if pair[1].utilization < pair[0].utilization {
+            pair[1].utilization
+        } else {
+            pair[1].utilization - pair[0].utilization
+        };
+        if delta > 0.0 {
+            let day = local_date(pair[1].at)?.weekday();
+            used[day.num_days_from_monday() as usize] += delta;
+        }
+    }
+
+    let mut days = [0u32; 7];
+    let mut date = local_date(first.at)?;
+    let today = local_date(now)?;
+    while date <= today {
+        days[date.weekday().num_days_from_monday() as usize] += 1;
+        date = date.succ_opt()?;
+    }
+
+    Some(std::array::from_fn(|day| {
+        used[day] / f64::from(days[day].max(1))
+    }))
+}
+
+/// The estimator for `model`, or `None` when the history is too thin and the
+/// linear pace should stand.
+pub fn estimator(
+    model: PaceModel,
+    window: &str,
+    samples: &[Sample],
+    now: DateTime<Utc>,
+) -> Option<Box<dyn PaceEstimator>> {
+    let now = now.timestamp();
+    match model {
+        PaceModel::Linear => None,
+        PaceModel::Recent => Some(Box::new(ConstantRate(recent_rate(samples, now)?))),
+        PaceModel::Smoothed => Some(Box::new(ConstantRate(smoothed_rate(samples)?))),
+        PaceModel::Weekday if window.starts_with("seven_day") => {
+            Some(Box::new(WeekdayRate(weekday_rates(samples, now)?)))
+        }
+        PaceModel::Weekday => Some(Box::new(ConstantRate(recent_rate(samples, now)?))),
+    }
+}
+
+/// Steps `estimator` forward from `now` until utilization reaches 100% or the
+/// window resets. Returns the minutes until it runs out, or `None` when it
+/// lasts to the reset.
+pub fn project(
+    estimator: &dyn PaceEstimator,
+    utilization: f64,
+    resets_in_minutes: f64,
+    now: DateTime<Utc>,
+) -> Option<f64> {
+    let mut remaining = 100.0 - utilization;
+    if remaining <= 0.0 {
+        return Some(0.0);
+    }
+    let mut minutes = 0.0;
+    while minutes < resets_in_minutes {
+        let step = PROJECTION_STEP_MINUTES.min(resets_in_minutes - minutes);
+        let at = now + chrono::Duration::seconds((minutes * 60.0) as i64);
+        let burn = estimator.rate_at(at) * step;
+        if burn >= remaining {
+            return Some(minutes + step * remaining / burn);
+        }
+        remaining -= burn;
+        minutes += step;
+    }
+    None
+}
+
+/// Replaces the run-out estimate (`eta_minutes`, `will_last_to_reset`) of
+/// each window that has a pace with `model`'s projection. Windows without
+/// enough history keep the linear estimate.
+pub fn apply(model: PaceModel, history: &History, data: &mut ActiveData, now: DateTime<Utc>) {
+    for (key, _, window) in data.windows_by_key_mut() {
+        let (Some(pace), Some(resets_in)) = (&mut window.pace, window.resets_in_minutes) else {
+            continue;
+        };
+        let Some(estimator) = estimator(model, key, history.samples(key), now) else {
+            continue;
+        };
+        let eta = project(estimator.as_ref(), window.utilization, resets_in, now);
+        pace.will_last_to_reset = eta.is_none();
+        pace.eta_minutes = eta;
+    }
+}
@@ -0,0 +1,219 @@
+use std::env;
+use std::fmt;
+use std::io::{self, Cursor, Write};
+use std::str::FromStr;
+
+use anyhow::{bail, Result};
+use base64::Engine;
+use image::imageops::{self, FilterType};
+use image::{Rgba, RgbaImage};
+
+/// How rendered screens are drawn in the terminal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PreviewMode {
+    /// Pick based on the terminal's environment variables.
+    Auto,
+    /// Kitty graphics protocol (kitty, WezTerm, Ghostty).
+    Kitty,
+    /// DEC sixel graphics (foot, mlterm, xterm -ti vt340).
+    Sixel,
+    /// Unicode half blocks with 24-bit color; works almost everywhere.
+    Blocks,
+}
+
+impl FromStr for PreviewMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Ok(match s {
+            "auto" => PreviewMode::Auto,
+            "kitty" => PreviewMode::Kitty,
+            "sixel" => PreviewMode::Sixel,
+            "blocks" => PreviewMode::Blocks,
+            other => {
+                bail!("unknown preview mode `{other}` (expected auto, kitty, sixel or blocks)")
+            }
+        })
+    }
+}
+
+impl fmt::Display for PreviewMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            PreviewMode::Auto => "auto",
+            PreviewMode::Kitty => "kitty",
+            PreviewMode::Sixel => "sixel",
+            PreviewMode::Blocks => "blocks",
+        })
+    }
+}
+
+impl PreviewMode {
+    /// Resolves `Auto` from `TERM`, `TERM_PROGRAM` and `KITTY_WINDOW_ID`.
+    pub fn resolve(self) -> PreviewMode {
+        if self != PreviewMode::Auto {
+            return self;
+        }
+        let term = env::var("TERM").unwrap_or_default();
+        let program = env::var("TERM_PROGRAM").unwrap_or_default();
+        if env::var_os("KITTY_WINDOW_ID").is_some()
+            || term.contains("kitty")
+            || matches!(program.as_str(), "WezTerm" | "ghostty")
+        {
+            PreviewMode::Kitty
+        } else if term.contains("sixel") || term.starts_with("foot") || term.starts_with("mlterm") {
+            PreviewMode::Sixel
+        } else {
+            PreviewMode::Blocks
+        }
+    }
+}
+
+/// Moves the cursor home and clears the screen before a redraw.
+pub fn clear_screen() -> Result<()> {
+    let mut out = io::stdout().lock();
+    out.write_all(b"\x1b[H\x1b[2J")?;
+    out.flush()?;
+    Ok(())
+}
+
+/// Draws `img` at the cursor position, followed by a newline.
+pub fn show(img: &RgbaImage, mode: PreviewMode) -> Result<()> {
+    let mut out = io::stdout().lock();
+    match mode.resolve() {
+        PreviewMode::Kitty => write_kitty(&mut out, img)?,
+        PreviewMode::Sixel => write_sixel(&mut out, img)?,
+        PreviewMode::Blocks | PreviewMode::Auto => write_blocks(&mut out, img, terminal_columns())?,
+    }
+    out.write_all(b"\n")?;
+    out.flush()?;
+    Ok(())
+}
+
+/// Writes `img` as a PNG with the kitty graphics protocol.
+pub fn write_kitty(out: &mut impl Write, img: &RgbaImage) -> Result<()> {
+    let mut png = Cursor::new(Vec::new());
+    img.write_to(&mut png, image::ImageFormat::Png)?;
+    let encoded = base64::engine::general_purpose::STANDARD.encode(png.into_inner());
+
+    // Payloads are sent in chunks of at most 4096 bytes; `m=1` marks more to come.
+    let chunks: Vec<&[u8]> = encoded.as_bytes().chunks(4096).collect();
+    for (i, chunk) in chunks.iter().enumerate() {
+        let more = u8::from(i + 1 < chunks.len());
+        if i == 0 {
+            write!(out, "\x1b_Gf=100,a=T,m={more};")?;
+        } else {
+            write!(out, "\x1b_Gm={more};")?;
+        }
+        out.write_all(chunk)?;
+        out.write_all(b"\x1b\\")?;
+    }
+    Ok(())
+}
+
+/// Index into a 6x6x6 color cube, the palette used for sixel output.
+fn cube_index(px: &Rgba<u8>) -> usize {
+    let level = |c: u8| (c as usize * 5 + 127) / 255;
+    level(px[0]) * 36 + level(px[1]) * 6 + level(px[2])
+}
+
+/// Writes `img` as sixels, quantized to a 6x6x6 color cube.
+pub fn write_sixel(out: &mut impl Write, img: &RgbaImage) -> Result<()> {
+    let (w, h) = img.dimensions();
+    write!(out, "\x1bPq\"1;1;{w};{h}")?;
+    for i in 0..216 {
+        let pct = |level: usize| level * 100 / 5;
+        write!(
+            out,
+            "#{i};2;{};{};{}",
+            pct(i / 36),
+            pct(i / 6 % 6),
+            pct(i % 6)
+        )?;
+    }
+
+    let indices: Vec<usize> = img.pixels().map(cube_index).collect();
+    for band in (0..h).step_by(6) {
+        let rows = (h - band).min(6);
+        let mut used = [false; 216];
+        for y in band..band + rows {
+            for x in 0..w {
+                used[indices[(y * w + x) as usize]] = true;
+            }
+        }
+
+        for color in (0..216).filter(|&c| used[c]) {
+            write!(out, "#{color}")?;
+            let mut run: Option<(u8, usize)> = None;
+            for x in 0..w {
+                let mut bits = 0u8;
+                for dy in 0..rows {
+                    if indices[((band + dy) * w + x) as usize] == color {
+                        bits |= 1 << dy;
+                    }
+                }
+                let ch = b'?' + bits;
+                run = match run {
+                    Some((prev, n)) if prev == ch => Some((prev, n + 1)),
+                    Some((prev, n)) => {
+                        write_sixel_run(out, prev, n)?;
+                        Some((ch, 1))
+                    }
+                    None => Some((ch, 1)),
+                };
+            }
+            if let Some((prev, n)) = run {
+                write_sixel_run(out, prev, n)?;
+            }
+            // Return to the start of the band for the next color.
+            out.write_all(b"$")?;
+        }
+        out.write_all(b"-")?;
+    }
+    out.write_all(b"\x1b\\")?;
+    Ok(())
+}
+
+fn write_sixel_run(out: &mut impl Write, ch: u8, n: usize) -> io::Result<()> {
+    if n > 3 {
+        write!(out, "!{n}{}", ch as char)
+    } else {
+        out.write_all(&vec![ch; n])
+    }
+}
+
+/// Terminal width in columns, from `COLUMNS` when the shell exports it.
+fn terminal_columns() -> u32 {
+    env::var("COLUMNS")
+        .ok()
+        .and_then(|c| c.parse().ok())
+        .unwrap_or(80)
+}
+
+/// Writes `img` as half-block characters, scaled to fit `columns` (at least
+/// 20, at most one per pixel).
+pub fn write_blocks(out: &mut impl Write, img: &RgbaImage, columns: u32) -> Result<()> {
+    // Each cell shows two pixels stacked vertically, and cells are roughly
+    // twice as tall as they are wide, so one column per pixel keeps the aspect.
+    let cols = columns.clamp(20, img.width());
+    let rows = (img.height() * cols / img.width()).max(2);
+    let small = imageops::resize(img, cols, rows, FilterType::Triangle);
+
+    for y in (0..small.height()).step_by(2) {
+        for x in 0..small.width() {
+            let top = small.get_pixel(x, y);
+            let bottom = if y + 1 < small.height() {
+                small.get_pixel(x, y + 1)
+            } else {
+                top
+            };
+            write!(
+                out,
+                "\x1b[38;2;{};{};{}m\x1b[48;2;{};{};{}m\u{2580}",
+                top[0], top[1], top[2], bottom[0], bottom[1], bottom[2]
+            )?;
+        }
+        out.write_all(b"\x1b[0m\n")?;
+    }
+    Ok(())
+}
@@ -1,32 +1,60 @@
 use ab_glyph::{FontRef, PxScale};
 use anyhow::Result;
+use chrono::{DateTime, Local, NaiveDateTime, TimeZone};
 use image::{Rgba, RgbaImage};
-use imageproc::drawing::draw_text_mut;
+use imageproc::drawing::{draw_text_mut, text_size};
 
-use crate::stats::{ActiveData, UsageWindow};
+use crate::budget::{Allowance, Period};
+use crate::device;
+use crate::stats::{ActiveData, UsageStatus, UsageWindow};
 
-const W: u32 = 240;
-const H: u32 = 240;
+pub(crate) const W: u32 = 240;
+pub(crate) const H: u32 = 240;
 
-const BG: Rgba<u8> = Rgba([12, 12, 16, 255]);
-const PANEL_BG: Rgba<u8> = Rgba([22, 22, 30, 255]);
-const TEXT_PRIMARY: Rgba<u8> = Rgba([240, 240, 245, 255]);
-const TEXT_DIM: Rgba<u8> = Rgba([113, 113, 122, 255]);
-const TEXT_MUTED: Rgba<u8> = Rgba([161, 161, 170, 255]);
+pub(crate) const BG: Rgba<u8> = Rgba([12, 12, 16, 255]);
+pub(crate) const PANEL_BG: Rgba<u8> = Rgba([22, 22, 30, 255]);
+pub(crate) const TEXT_PRIMARY: Rgba<u8> = Rgba([240, 240, 245, 255]);
+pub(crate) const TEXT_DIM: Rgba<u8> = Rgba([113, 113, 122, 255]);
+pub(crate) const TEXT_MUTED: Rgba<u8> = Rgba([161, 161, 170, 255]);
 const BAR_TRACK: Rgba<u8> = Rgba([40, 40, 50, 255]);
 const BAR_FILL_LEFT: Rgba<u8> = Rgba([59, 130, 246, 255]);
 const BAR_FILL_RIGHT: Rgba<u8> = Rgba([6, 182, 212, 255]);
 const PACE_OK: Rgba<u8> = Rgba([34, 197, 94, 255]);
 const PACE_WARN: Rgba<u8> = Rgba([249, 115, 22, 255]);
-const WARN_FILL_LEFT: Rgba<u8> = Rgba([234, 179, 8, 255]);
-const WARN_FILL_RIGHT: Rgba<u8> = Rgba([249, 115, 22, 255]);
-const DANGER_FILL: Rgba<u8> = Rgba([239, 68, 68, 255]);
-const SEPARATOR: Rgba<u8> = Rgba([35, 35, 45, 255]);
+pub(crate) const WARN_FILL_LEFT: Rgba<u8> = Rgba([234, 179, 8, 255]);
+pub(crate) const WARN_FILL_RIGHT: Rgba<u8> = Rgba([249, 115, 22, 255]);
+pub(crate) const DANGER_FILL: Rgba<u8> = Rgba([239, 68, 68, 255]);
+pub(crate) const SEPARATOR: Rgba<u8> = Rgba([35, 35, 45, 255]);
+pub(crate) const GRAPH_BG: Rgba<u8> = Rgba([16, 16, 22, 255]);
 
-const FONT_BYTES: &[u8] = include_bytes!("../fonts/Inter-Regular.ttf");
-const FONT_BOLD_BYTES: &[u8] = include_bytes!("../fonts/Inter-Bold.ttf");
+/// Default for the `stale_after` setting, in minutes.
+pub const DEFAULT_STALE_AFTER_MINUTES: u64 = 30;
 
-fn lerp_color(a: Rgba<u8>, b: Rgba<u8>, t: f32) -> Rgba<u8> {
+/// Settings shared by the stats renderers.
+#[derive(Debug, Clone)]
+pub struct RenderOptions {
+    /// Ordered-dither gradients to RGB565.
+    pub dither: bool,
+    /// Reference time for data age; fixed in tests.
+    pub now: DateTime<Local>,
+    /// Data older than this many minutes is drawn as stale.
+    pub stale_after_minutes: u64,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        RenderOptions {
+            dither: false,
+            now: Local::now(),
+            stale_after_minutes: DEFAULT_STALE_AFTER_MINUTES,
+        }
+    }
+}
+
+pub(crate) const FONT_BYTES: &[u8] = include_bytes!("../fonts/Inter-Regular.ttf");
+pub(crate) const FONT_BOLD_BYTES: &[u8] = include_bytes!("../fonts/Inter-Bold.ttf");
+
+pub(crate) fn lerp_color(a: Rgba<u8>, b: Rgba<u8>, t: f32) -> Rgba<u8> {
     let t = t.clamp(0.0, 1.0);
     Rgba([
         (a[0] as f32 + (b[0] as f32 - a[0] as f32) * t) as u8,
@@ -67,8 +95,8 @@ fn is_inside_rounded(px: u32, py: u32, w: u32, h: u32, r: u32) -> bool {
             py > h.saturating_sub(r + 1)
         };
         if in_corner_x && in_corner_y {
-            let dx = if px < cx { cx - px } else { px - cx };
-            let dy = if py < cy { cy - py } else { py - cy };
+            let dx = cx.abs_diff(px);
+            let dy = cy.abs_diff(py);
             if dx * dx + dy * dy > r * r {
                 return false;
             }
@@ -77,7 +105,15 @@ fn is_inside_rounded(px: u32, py: u32, w: u32, h: u32, r: u32) -> bool {
     true
 }
 
-fn draw_rounded_rect(img: &mut RgbaImage, x: i32, y: i32, w: u32, h: u32, r: u32, color: Rgba<u8>) {
+pub(crate) fn draw_rounded_rect(
+    img: &mut RgbaImage,
+    x: i32,
+    y: i32,
+    w: u32,
+    h: u32,
+    r: u32,
+    color: Rgba<u8>,
+) {
     for px in 0..w {
         for py in 0..h {
             if is_inside_rounded(px, py, w, h, r) {
@@ -91,7 +127,8 @@ fn draw_rounded_rect(img: &mut RgbaImage, x: i32, y: i32, w: u32, h: u32, r: u32
     }
 }
 
-fn draw_gradient_bar(
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn draw_gradient_bar(
     img: &mut RgbaImage,
     x: i32,
     y: i32,
@@ -101,6 +138,7 @@ fn draw_gradient_bar(
     left_color: Rgba<u8>,
     right_color: Rgba<u8>,
     corner_r: u32,
+    dither: bool,
 ) {
     draw_rounded_rect(img, x, y, total_w, h, corner_r, BAR_TRACK);
     let fill_w = ((total_w as f32) * fill_frac.clamp(0.0, 1.0)) as u32;
@@ -118,12 +156,114 @@ fn draw_gradient_bar(
         for py in 0..h {
             let abs_y = y as u32 + py;
             if is_inside_rounded(px, py, fill_w, h, corner_r) && abs_x < W && abs_y < H {
+                let color = if dither {
+                    device::dither_rgb565(color, abs_x, abs_y)
+                } else {
+                    color
+                };
                 img.put_pixel(abs_x, abs_y, color);
             }
         }
     }
 }
 
+/// A history graph panel: `len` samples span the inner width, newest on the
+/// right, scaled so `max` fills the height.
+pub(crate) struct HistoryGraph {
+    x: u32,
+    y: u32,
+    inner_w: u32,
+    inner_h: u32,
+    step: f64,
+    max: f64,
+    dither: bool,
+}
+
+impl HistoryGraph {
+    /// Draws the empty panel and returns the graph to plot into.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn new(
+        img: &mut RgbaImage,
+        x: i32,
+        y: i32,
+        w: u32,
+        h: u32,
+        len: usize,
+        max: f64,
+        dither: bool,
+    ) -> Self {
+        draw_rounded_rect(img, x, y, w, h, 4, GRAPH_BG);
+        let inner_w = w - 4;
+        HistoryGraph {
+            x: (x + 2) as u32,
+            y: (y + 2) as u32,
+            inner_w,
+            inner_h: h - 4,
+            step: inner_w as f64 / (len - 1) as f64,
+            max,
+            dither,
+        }
+    }
+
+    pub(crate) fn inner_w(&self) -> u32 {
+        self.inner_w
+    }
+
+    pub(crate) fn inner_h(&self) -> u32 {
+        self.inner_h
+    }
+
+    /// Filled height at column `px`, interpolating between samples, or
+    /// `None` left of the oldest one.
+    pub(crate) fn height_at(&self, values: &[f64], px: u32) -> Option<f64> {
+        if values.is_empty() || self.max <= 0.0 {
+            return None;
+        }
+        // Position in the history, counted back from the newest sample.
+        let back = (self.inner_w as f64 - 1.0 - px as f64) / self.step;
+        let newest = values.len() - 1;
+        if back > newest as f64 {
+            return None;
+        }
+        let i = newest as f64 - back;
+        let (lo, hi) = (i.floor() as usize, (i.ceil() as usize).min(newest));
+        let v = values[lo] + (values[hi] - values[lo]) * (i - lo as f64);
+        Some((v / self.max).clamp(0.0, 1.0) * self.inner_h as f64)
+    }
+
+    /// Fills the area under `values`: a solid top edge fading towards the
+    /// bottom.
+    pub(crate) fn fill_area(&self, img: &mut RgbaImage, values: &[f64], color: Rgba<u8>) {
+        let faded = lerp_color(GRAPH_BG, color, 0.45);
+        for px in 0..self.inner_w {
+            let Some(filled) = self.height_at(values, px) else {
+                continue;
+            };
+            let top = (self.inner_h as f64 - filled).round() as u32;
+            for py in top..self.inner_h {
+                let color = if py <= top + 1 {
+                    color
+                } else {
+                    let t = (py - top) as f32 / self.inner_h as f32;
+                    lerp_color(faded, GRAPH_BG, t * 0.7)
+                };
+                self.put(img, px, py, color);
+            }
+        }
+    }
+
+    /// Sets a pixel at inner coordinates.
+    pub(crate) fn put(&self, img: &mut RgbaImage, px: u32, py: u32, color: Rgba<u8>) {
+        let (abs_x, abs_y) = (self.x + px, self.y + py);
+        let color = if self.dither {
+            device::dither_rgb565(color, abs_x, abs_y)
+        } else {
+            color
+        };
+        img.put_pixel(abs_x, abs_y, color);
+    }
+}
+
 fn blend_over(base: Rgba<u8>, over: Rgba<u8>) -> Rgba<u8> {
     let a = over[3] as f32 / 255.0;
     Rgba([
@@ -221,7 +361,7 @@ fn approx_text_width(text: &str, scale: f32) -> i32 {
     w.ceil() as i32
 }
 
-fn draw_text_right(
+pub(crate) fn draw_text_right(
     img: &mut RgbaImage,
     color: Rgba<u8>,
     right_x: i32,
@@ -234,47 +374,142 @@ fn draw_text_right(
     draw_text_mut(img, color, right_x - w, y, PxScale::from(scale), font, text);
 }
 
-fn format_updated_time(iso: &str) -> String {
-    use chrono::{DateTime, Local};
-    if let Ok(utc) = DateTime::parse_from_rfc3339(iso) {
-        let local: DateTime<Local> = utc.with_timezone(&Local);
-        return local.format("%H:%M").to_string();
-    }
-    // Fallback: try extracting time part
-    if let Some(t_pos) = iso.find('T') {
-        let time_part = &iso[t_pos + 1..];
-        if time_part.len() >= 5 {
-            return time_part[..5].to_string();
+fn draw_text_centered(
+    img: &mut RgbaImage,
+    color: Rgba<u8>,
+    y: i32,
+    scale: f32,
+    font: &FontRef,
+    text: &str,
+) {
+    let (w, _) = text_size(PxScale::from(scale), font, text);
+    let x = (W as i32 - w as i32) / 2;
+    draw_text_mut(img, color, x, y, PxScale::from(scale), font, text);
+}
+
+/// Greedy word wrap by approximate width; the last line is cut with an
+/// ellipsis if the text doesn't fit in `max_lines`.
+fn wrap_text(text: &str, scale: f32, max_w: i32, max_lines: usize) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    let mut current = String::new();
+    for word in text.split_whitespace() {
+        // Break words too long for a line on their own (URLs, paths).
+        let mut word = word.to_string();
+        while approx_text_width(&word, scale) > max_w {
+            let mut head = String::new();
+            for ch in word.chars() {
+                head.push(ch);
+                if approx_text_width(&head, scale) > max_w {
+                    head.pop();
+                    break;
+                }
+            }
+            if head.is_empty() {
+                break;
+            }
+            let tail: String = word.chars().skip(head.chars().count()).collect();
+            if !current.is_empty() {
+                lines.push(std::mem::take(&mut current));
+            }
+            lines.push(head);
+            word = tail;
+        }
+        let word = word.as_str();
+        let candidate = if current.is_empty() {
+            word.to_string()
+        } else {
+            format!("{current} {word}")
+        };
+        if approx_text_width(&candidate, scale) <= max_w || current.is_empty() {
+            current = candidate;
+        } else {
+            lines.push(std::mem::replace(&mut current, word.to_string()));
+        }
+    }
+    if !current.is_empty() {
+        lines.push(current);
+    }
+    if lines.len() > max_lines {
+        lines.truncate(max_lines);
+        let last = &mut lines[max_lines - 1];
+        while !last.is_empty() && approx_text_width(&format!("{last}…"), scale) > max_w {
+            last.pop();
         }
+        last.push('…');
     }
-    "??:??".to_string()
+    lines
 }
 
-struct BarSection {
-    label: &'static str,
-    window: UsageWindow,
+/// Parses `updated_at`. Timestamps without an offset are taken as local time.
+fn parse_timestamp(iso: &str) -> Option<DateTime<Local>> {
+    if let Ok(ts) = DateTime::parse_from_rfc3339(iso) {
+        return Some(ts.with_timezone(&Local));
+    }
+    let naive = NaiveDateTime::parse_from_str(iso, "%Y-%m-%dT%H:%M:%S%.f").ok()?;
+    Local.from_local_datetime(&naive).earliest()
 }
 
-pub fn render_bars(data: &ActiveData) -> Result<RgbaImage> {
-    let font = FontRef::try_from_slice(FONT_BYTES)?;
-    let font_bold = FontRef::try_from_slice(FONT_BOLD_BYTES)?;
-    let mut img = RgbaImage::from_pixel(W, H, BG);
-
-    let mut sections: Vec<BarSection> = Vec::new();
-    if let Some(w) = &data.five_hour {
-        sections.push(BarSection {
-            label: "Session",
-            window: w.clone(),
-        });
+fn format_age(minutes: i64) -> String {
+    if minutes < 60 {
+        format!("{}m", minutes.max(0))
+    } else if minutes < 48 * 60 {
+        format!("{}h", minutes / 60)
+    } else {
+        format!("{}d", minutes / 1440)
     }
-    if let Some(w) = &data.seven_day {
-        sections.push(BarSection {
-            label: "Weekly",
-            window: w.clone(),
-        });
+}
+
+/// How fresh a timestamp is relative to [`RenderOptions::now`].
+struct Freshness {
+    /// "14:32", "12m ago", or with the date prefixed when not from today.
+    label: String,
+    stale: bool,
+}
+
+fn freshness(iso: &str, opts: &RenderOptions) -> Freshness {
+    let Some(ts) = parse_timestamp(iso) else {
+        return Freshness {
+            label: "??:??".to_string(),
+            stale: false,
+        };
+    };
+    let age = (opts.now - ts).num_minutes();
+    let stale = age >= opts.stale_after_minutes as i64;
+    let time = if stale {
+        format!("{} ago", format_age(age))
+    } else {
+        ts.format("%H:%M").to_string()
+    };
+    let label = if ts.date_naive() == opts.now.date_naive() {
+        time
+    } else {
+        format!("{} · {time}", ts.format("%b %-d"))
+    };
+    Freshness { label, stale }
+}
+
+/// Fades everything from `top` down toward the background.
+fn dim_below(img: &mut RgbaImage, top: u32) {
+    for y in top..H {
+        for x in 0..W {
+            let px = *img.get_pixel(x, y);
+            img.put_pixel(x, y, lerp_color(px, BG, 0.55));
+        }
     }
+}
 
-    if sections.is_empty() {
+/// Usage windows per stats page.
+const BARS_PER_PAGE: usize = 2;
+
+/// Bars for every usage window, session and weekly first, two to a page.
+/// With more than one page the header shows the page number.
+pub fn render_bars(data: &ActiveData, opts: &RenderOptions) -> Result<Vec<RgbaImage>> {
+    let font = FontRef::try_from_slice(FONT_BYTES)?;
+    let font_bold = FontRef::try_from_slice(FONT_BOLD_BYTES)?;
+
+    let windows = data.windows();
+    if windows.is_empty() {
+        let mut img = RgbaImage::from_pixel(W, H, BG);
         draw_text_mut(
             &mut img,
             TEXT_DIM,
@@ -284,9 +519,31 @@ pub fn render_bars(data: &ActiveData) -> Result<RgbaImage> {
             &font,
             "No usage data",
         );
-        return Ok(img);
+        return Ok(vec![img]);
     }
 
+    let pages = windows.len().div_ceil(BARS_PER_PAGE);
+    Ok(windows
+        .chunks(BARS_PER_PAGE)
+        .enumerate()
+        .map(|(i, sections)| {
+            let page = (pages > 1).then(|| format!("{}/{pages}", i + 1));
+            render_bar_page(data, sections, page.as_deref(), opts, &font, &font_bold)
+        })
+        .collect())
+}
+
+fn render_bar_page(
+    data: &ActiveData,
+    sections: &[(&str, &UsageWindow)],
+    page: Option<&str>,
+    opts: &RenderOptions,
+    font: &FontRef,
+    font_bold: &FontRef,
+) -> RgbaImage {
+    let dither = opts.dither;
+    let mut img = RgbaImage::from_pixel(W, H, BG);
+
     let mx = 16i32;
     let right_edge = (W as i32) - mx;
     let content_w = (right_edge - mx) as u32;
@@ -299,24 +556,38 @@ pub fn render_bars(data: &ActiveData) -> Result<RgbaImage> {
         mx,
         header_y,
         PxScale::from(17.0),
-        &font_bold,
+        font_bold,
         "Claude Code",
     );
+    if let Some(page) = page {
+        let title_w = approx_text_width("Claude Code", 17.0);
+        draw_text_mut(
+            &mut img,
+            TEXT_DIM,
+            mx + title_w + 8,
+            header_y + 3,
+            PxScale::from(13.0),
+            font,
+            page,
+        );
+    }
 
-    // Updated timestamp (right-aligned, bigger)
-    let updated_text = if let Some(ts) = &data.updated_at {
-        format_updated_time(ts)
-    } else {
-        "—".to_string()
+    // Updated timestamp (right-aligned, bigger); relative age once stale
+    let fresh = data.updated_at.as_deref().map(|ts| freshness(ts, opts));
+    let (updated_text, updated_color, updated_scale) = match &fresh {
+        Some(f) if f.stale => (f.label.as_str(), PACE_WARN, 13.0),
+        Some(f) if f.label.len() > 5 => (f.label.as_str(), TEXT_DIM, 13.0),
+        Some(f) => (f.label.as_str(), TEXT_DIM, 15.0),
+        None => ("—", TEXT_DIM, 15.0),
     };
     draw_text_right(
         &mut img,
-        TEXT_DIM,
+        updated_color,
         right_edge,
-        header_y + 1,
-        15.0,
-        &font,
-        &updated_text,
+        header_y + 1 + (15.0 - updated_scale) as i32,
+        updated_scale,
+        font,
+        updated_text,
     );
 
     // Separator
@@ -327,9 +598,8 @@ pub fn render_bars(data: &ActiveData) -> Result<RgbaImage> {
     let gap = 1i32; // tighter gap between sections
     let start_y = 37; // moved up
 
-    for (i, section) in sections.iter().enumerate() {
+    for (i, &(label, w)) in sections.iter().enumerate() {
         let by = start_y + (i as i32) * (section_h + gap);
-        let w = &section.window;
         let bar_x = mx + 8;
         let bar_w = content_w - 16;
         let inner_right = right_edge - 6;
@@ -353,8 +623,8 @@ pub fn render_bars(data: &ActiveData) -> Result<RgbaImage> {
             bar_x,
             row1_y + 10,
             PxScale::from(14.0),
-            &font_bold,
-            section.label,
+            font_bold,
+            label,
         );
 
         let pct_val = w.utilization.round() as i32;
@@ -365,7 +635,7 @@ pub fn render_bars(data: &ActiveData) -> Result<RgbaImage> {
             inner_right,
             row1_y - 2,
             36.0,
-            &font_bold,
+            font_bold,
             &pct_text,
         );
 
@@ -375,7 +645,7 @@ pub fn render_bars(data: &ActiveData) -> Result<RgbaImage> {
         let fill_frac = (w.utilization / 100.0) as f32;
         let (fill_l, fill_r) = bar_colors(&w.usage_level);
         draw_gradient_bar(
-            &mut img, bar_x, bar_y, bar_w, bar_h, fill_frac, fill_l, fill_r, 7,
+            &mut img, bar_x, bar_y, bar_w, bar_h, fill_frac, fill_l, fill_r, 7, dither,
         );
 
         // Pace marker on bar
@@ -401,7 +671,7 @@ pub fn render_bars(data: &ActiveData) -> Result<RgbaImage> {
             bar_x,
             row3_y,
             PxScale::from(15.0),
-            &font_bold,
+            font_bold,
             &left_text,
         );
 
@@ -413,7 +683,7 @@ pub fn render_bars(data: &ActiveData) -> Result<RgbaImage> {
                 inner_right,
                 row3_y + 1,
                 15.0,
-                &font,
+                font,
                 &reset_text,
             );
         }
@@ -438,7 +708,7 @@ pub fn render_bars(data: &ActiveData) -> Result<RgbaImage> {
                 bar_x + 12,
                 pace_y,
                 PxScale::from(13.0),
-                &font,
+                font,
                 &pace_text,
             );
 
@@ -457,12 +727,346 @@ pub fn render_bars(data: &ActiveData) -> Result<RgbaImage> {
                     inner_right,
                     pace_y,
                     12.0,
-                    &font,
+                    font,
                     &right_text,
                 );
             }
         }
     }
 
+    if fresh.is_some_and(|f| f.stale) {
+        dim_below(&mut img, 35);
+    }
+
+    img
+}
+
+/// Pages with how much of each window can be used per hour (windows of up
+/// to a day) or per day (longer ones) to last until it resets, two windows
+/// to a page. Windows without an allowance are left out.
+pub fn render_budget(data: &ActiveData, opts: &RenderOptions) -> Result<Vec<RgbaImage>> {
+    let font = FontRef::try_from_slice(FONT_BYTES)?;
+    let font_bold = FontRef::try_from_slice(FONT_BOLD_BYTES)?;
+
+    let sections: Vec<(&str, &UsageWindow, &Allowance)> = data
+        .windows()
+        .into_iter()
+        .filter_map(|(label, w)| Some((label, w, w.allowance.as_ref()?)))
+        .collect();
+    if sections.is_empty() {
+        let mut img = render_budget_page(data, &[], None, opts, &font, &font_bold);
+        draw_text_centered(&mut img, TEXT_DIM, 110, 16.0, &font, "No reset times");
+        return Ok(vec![img]);
+    }
+
+    let pages = sections.len().div_ceil(BARS_PER_PAGE);
+    Ok(sections
+        .chunks(BARS_PER_PAGE)
+        .enumerate()
+        .map(|(i, sections)| {
+            let page = (pages > 1).then(|| format!("{}/{pages}", i + 1));
+            render_budget_page(data, sections, page.as_deref(), opts, &font, &font_bold)
+        })
+        .collect())
+}
+
+fn render_budget_page(
+    data: &ActiveData,
+    sections: &[(&str, &UsageWindow, &Allowance)],
+    page: Option<&str>,
+    opts: &RenderOptions,
+    font: &FontRef,
+    font_bold: &FontRef,
+) -> RgbaImage {
+    let mut img = RgbaImage::from_pixel(W, H, BG);
+
+    let mx = 16i32;
+    let right_edge = (W as i32) - mx;
+    let content_w = (right_edge - mx) as u32;
+
+    // ── Header: "Budget" + page + updated time ──
+    let header_y = 10;
+    draw_text_mut(
+        &mut img,
+        TEXT_PRIMARY,
+        mx,
+        header_y,
+        PxScale::from(17.0),
+        font_bold,
+        "Budget",
+    );
+    if let Some(page) = page {
+        let title_w = approx_text_width("Budget", 17.0);
+        draw_text_mut(
+            &mut img,
+            TEXT_DIM,
+            mx + title_w + 8,
+            header_y + 3,
+            PxScale::from(13.0),
+            font,
+            page,
+        );
+    }
+    let fresh = data.updated_at.as_deref().map(|ts| freshness(ts, opts));
+    if let Some(f) = &fresh {
+        let (color, scale) = match f {
+            f if f.stale => (PACE_WARN, 13.0),
+            f if f.label.len() > 5 => (TEXT_DIM, 13.0),
+            _ => (TEXT_DIM, 15.0),
+        };
+        draw_text_right(
+            &mut img,
+            color,
+            right_edge,
+            header_y + 1 + (15.0 - scale) as i32,
+            scale,
+            font,
+            &f.label,
+        );
+    }
+    draw_rounded_rect(&mut img, mx, 33, content_w, 1, 0, SEPARATOR);
+
+    let section_h = 98i32;
+    let start_y = 37;
+    for (i, &(label, w, allowance)) in sections.iter().enumerate() {
+        let by = start_y + (i as i32) * (section_h + 1);
+        let left_x = mx + 8;
+        let inner_right = right_edge - 6;
+        draw_rounded_rect(
+            &mut img,
+            mx - 4,
+            by - 2,
+            content_w + 8,
+            section_h as u32 + 4,
+            10,
+            PANEL_BG,
+        );
+
+        // Row 1: label left, allowance big on the right with its unit below
+        let row1_y = by + 4;
+        draw_text_mut(
+            &mut img,
+            TEXT_MUTED,
+            left_x,
+            row1_y + 10,
+            PxScale::from(14.0),
+            font_bold,
+            label,
+        );
+        let exhausted = allowance.percent <= 0.0;
+        let amount = if allowance.percent >= 10.0 || exhausted {
+            format!("{}%", allowance.percent.round() as i32)
+        } else {
+            format!("{:.1}%", allowance.percent)
+        };
+        draw_text_right(
+            &mut img,
+            if exhausted { DANGER_FILL } else { TEXT_PRIMARY },
+            inner_right,
+            row1_y - 2,
+            36.0,
+            font_bold,
+            &amount,
+        );
+        let unit = match allowance.period {
+            Period::Hour => "per hour",
+            Period::Day => "per day",
+            Period::WorkingDay => "per work day",
+        };
+        draw_text_right(
+            &mut img,
+            TEXT_MUTED,
+            inner_right,
+            row1_y + 40,
+            13.0,
+            font,
+            unit,
+        );
+
+        // Row 2: what's left and how many periods it spreads over
+        let row2_y = row1_y + 62;
+        let left_text = if exhausted {
+            "Limit reached".to_string()
+        } else {
+            format!("{}% left", (100.0 - w.utilization).max(0.0).round() as i32)
+        };
+        draw_text_mut(
+            &mut img,
+            if exhausted { DANGER_FILL } else { TEXT_PRIMARY },
+            left_x,
+            row2_y,
+            PxScale::from(15.0),
+            font_bold,
+            &left_text,
+        );
+        draw_text_right(
+            &mut img,
+            TEXT_DIM,
+            inner_right,
+            row2_y + 1,
+            13.0,
+            font,
+            &allowance.period.describe(allowance.periods),
+        );
+
+        // Row 3: reset time
+        if let Some(mins) = w.resets_in_minutes {
+            let reset_text = format!("resets {}", format_duration(mins));
+            draw_text_mut(
+                &mut img,
+                TEXT_DIM,
+                left_x,
+                row2_y + 18,
+                PxScale::from(12.0),
+                font,
+                &reset_text,
+            );
+        }
+    }
+
+    if fresh.is_some_and(|f| f.stale) {
+        dim_below(&mut img, 35);
+    }
+
+    img
+}
+
+fn status_text(status: &UsageStatus) -> (&'static str, &'static str, Rgba<u8>) {
+    match status {
+        UsageStatus::Active => ("Active", "", PACE_OK),
+        UsageStatus::LoggedOut => (
+            "Logged out",
+            "Sign in to Claude Code to resume usage stats.",
+            WARN_FILL_LEFT,
+        ),
+        UsageStatus::RateLimited => (
+            "Rate limited",
+            "The usage API is throttling requests. Retrying on the next update.",
+            PACE_WARN,
+        ),
+        UsageStatus::NetworkError => (
+            "Offline",
+            "Couldn't reach the usage API. Retrying on the next update.",
+            DANGER_FILL,
+        ),
+        UsageStatus::Unknown(_) => ("No usage data", "", TEXT_MUTED),
+    }
+}
+
+/// Screen shown instead of the bars when the source reports no usage data.
+///
+/// `message` is the source's own explanation, shown in place of the default
+/// hint; `last_good` is the `updated_at` of the last active payload.
+pub fn render_status(
+    status: &UsageStatus,
+    message: Option<&str>,
+    last_good: Option<&str>,
+    opts: &RenderOptions,
+) -> Result<RgbaImage> {
+    let font = FontRef::try_from_slice(FONT_BYTES)?;
+    let font_bold = FontRef::try_from_slice(FONT_BOLD_BYTES)?;
+    let mut img = RgbaImage::from_pixel(W, H, BG);
+
+    let mx = 16i32;
+    let right_edge = (W as i32) - mx;
+    let content_w = (right_edge - mx) as u32;
+
+    // ── Header, same as the bars screen ──
+    draw_text_mut(
+        &mut img,
+        TEXT_PRIMARY,
+        mx,
+        10,
+        PxScale::from(17.0),
+        &font_bold,
+        "Claude Code",
+    );
+    draw_rounded_rect(&mut img, mx, 33, content_w, 1, 0, SEPARATOR);
+
+    // ── Icon: colored ring with an exclamation mark ──
+    let (title, hint, color) = status_text(status);
+    let (cx, cy) = (W as i32 / 2, 80);
+    draw_circle(&mut img, cx, cy, 26, color);
+    draw_circle(&mut img, cx, cy, 22, BG);
+    draw_text_centered(&mut img, color, cy - 19, 34.0, &font_bold, "!");
+
+    draw_text_centered(&mut img, TEXT_PRIMARY, 118, 20.0, &font_bold, title);
+
+    let detail = match (message, status) {
+        (Some(m), _) if !m.trim().is_empty() => m.trim().to_string(),
+        (_, UsageStatus::Unknown(raw)) => format!("Source reported status \"{raw}\"."),
+        _ => hint.to_string(),
+    };
+    let mut line_y = 148;
+    for line in wrap_text(&detail, 13.0, content_w as i32, 3) {
+        draw_text_centered(&mut img, TEXT_MUTED, line_y, 13.0, &font, &line);
+        line_y += 16;
+    }
+
+    // ── Footer: when the data was last good ──
+    draw_rounded_rect(&mut img, mx, 204, content_w, 1, 0, SEPARATOR);
+    let footer = match last_good {
+        Some(ts) => format!("Last data {}", freshness(ts, opts).label),
+        None => "No data yet".to_string(),
+    };
+    draw_text_centered(&mut img, TEXT_DIM, 212, 14.0, &font, &footer);
+
     Ok(img)
 }
+
+const ALERT_BG: Rgba<u8> = Rgba([153, 27, 27, 255]);
+const ALERT_OVER_BG: Rgba<u8> = Rgba([127, 0, 0, 255]);
+const ALERT_TEXT: Rgba<u8> = Rgba([255, 255, 255, 255]);
+const ALERT_TEXT_DIM: Rgba<u8> = Rgba([254, 202, 202, 255]);
+
+/// High-contrast page shown in place of the album while a window is in
+/// `danger` or `over`. Returns `None` when no window is critical.
+pub fn render_alert(data: &ActiveData) -> Result<Option<RgbaImage>> {
+    let windows = data.windows();
+    // The most used critical window takes the page; the next one gets a
+    // footer.
+    let Some(&(label, w)) = windows
+        .iter()
+        .filter(|(_, w)| w.is_critical())
+        .max_by(|a, b| a.1.utilization.total_cmp(&b.1.utilization))
+    else {
+        return Ok(None);
+    };
+    let other = windows.iter().find(|(l, _)| *l != label);
+
+    let font = FontRef::try_from_slice(FONT_BYTES)?;
+    let font_bold = FontRef::try_from_slice(FONT_BOLD_BYTES)?;
+    let over = w.usage_level == "over";
+    let mut img = RgbaImage::from_pixel(W, H, if over { ALERT_OVER_BG } else { ALERT_BG });
+
+    let heading = if over { "LIMIT REACHED" } else { "NEAR LIMIT" };
+    draw_text_centered(&mut img, ALERT_TEXT, 14, 18.0, &font_bold, heading);
+    draw_text_centered(&mut img, ALERT_TEXT_DIM, 48, 20.0, &font_bold, label);
+
+    let pct_text = format!("{}%", w.utilization.round() as i32);
+    draw_text_centered(&mut img, ALERT_TEXT, 70, 72.0, &font_bold, &pct_text);
+
+    let mut line_y = 158;
+    if let Some(mins) = w.resets_in_minutes {
+        let reset_text = format!("resets in {}", format_duration(mins));
+        draw_text_centered(&mut img, ALERT_TEXT, line_y, 17.0, &font, &reset_text);
+        line_y += 22;
+    }
+    if let Some(eta) = w
+        .pace
+        .as_ref()
+        .filter(|p| !p.will_last_to_reset)
+        .and_then(|p| p.eta_minutes)
+        .filter(|_| !over)
+    {
+        let eta_text = format!("Out in {}", format_duration(eta));
+        draw_text_centered(&mut img, ALERT_TEXT, line_y, 17.0, &font_bold, &eta_text);
+    }
+
+    if let Some((other_label, other)) = other {
+        let other_text = format!("{other_label} {}%", other.utilization.round() as i32);
+        draw_text_centered(&mut img, ALERT_TEXT_DIM, 212, 14.0, &font, &other_text);
+    }
+
+    Ok(Some(img))
+}
@@ -0,0 +1,55 @@
+use anyhow::Result;
+use serde::Serialize;
+
+use crate::disk_render::DiskInfo;
+use crate::network_render::NetworkInfo;
+use crate::sensors_render::SensorInfo;
+use crate::stats::ActiveData;
+use crate::system_render::SystemInfo;
+
+/// What a one-shot run collected and pushed, printed by `--json`. Sections
+/// that don't apply to the run (no disk page, nothing uploaded) are left out.
+#[derive(Debug, Default, Serialize)]
+pub struct Report {
+    /// Payload status (`active`, `logged out`, ...).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<String>,
+    /// The source's explanation when the status isn't `active`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    /// Usage windows, with pace filled in locally where the source left it out.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub usage: Option<ActiveData>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub disk: Option<DiskInfo>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub system: Option<SystemInfo>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub network: Option<NetworkInfo>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sensors: Option<SensorInfo>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub upload: Option<UploadResult>,
+    /// Why the run failed, if it did, or else why the fetch failed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct UploadResult {
+    pub host: String,
+    /// Pages sent (`stats`, `budget`, `disk`, `system`, ..., or `alert`), in
+    /// album order.
+    pub pages: Vec<String>,
+    /// JPEG bytes sent.
+    pub bytes: usize,
+    pub seconds: f64,
+}
+
+impl Report {
+    /// Writes the report to stdout as pretty-printed JSON.
+    pub fn print(&self) -> Result<()> {
+        println!("{}", serde_json::to_string_pretty(self)?);
+        Ok(())
+    }
+}
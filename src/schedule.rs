@@ -0,0 +1,212 @@
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::{
+    DateTime, Datelike, Duration, Local, NaiveDate, NaiveDateTime, TimeZone, Utc, Weekday,
+};
+use chrono_tz::Tz;
+
+use crate::stats::{self, ActiveData, PaceInfo};
+
+/// Time zone the working hours are given in.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Zone {
+    Local,
+    Named(Tz),
+}
+
+impl Zone {
+    fn date_of(self, at: DateTime<Utc>) -> NaiveDate {
+        match self {
+            Zone::Local => at.with_timezone(&Local).date_naive(),
+            Zone::Named(tz) => at.with_timezone(&tz).date_naive(),
+        }
+    }
+
+    /// The earlier instant for times repeated by a DST change; times it
+    /// skips move forward by the hour the clock jumped.
+    fn utc_of(self, naive: NaiveDateTime) -> Option<DateTime<Utc>> {
+        let resolve = |naive: NaiveDateTime| match self {
+            Zone::Local => Local
+                .from_local_datetime(&naive)
+                .earliest()
+                .map(|t| t.with_timezone(&Utc)),
+            Zone::Named(tz) => tz
+                .from_local_datetime(&naive)
+                .earliest()
+                .map(|t| t.with_timezone(&Utc)),
+        };
+        resolve(naive).or_else(|| resolve(naive + Duration::hours(1)))
+    }
+}
+
+/// When usage is expected to happen, from the `working_hours`,
+/// `working_days` and `timezone` settings.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WorkingHours {
+    /// Minutes after midnight.
+    start: u32,
+    /// Minutes after midnight, up to 1440; at or before `start` for hours
+    /// that run past midnight.
+    end: u32,
+    /// Monday first.
+    days: [bool; 7],
+    zone: Zone,
+}
+
+fn parse_clock(s: &str) -> Result<u32> {
+    let (h, m) = s
+        .trim()
+        .split_once(':')
+        .ok_or_else(|| anyhow!("expected HH:MM, got `{s}`"))?;
+    let (h, m): (u32, u32) = (h.parse()?, m.parse()?);
+    if m >= 60 || h * 60 + m > 1440 {
+        bail!("`{s}` is not a time of day");
+    }
+    Ok(h * 60 + m)
+}
+
+impl WorkingHours {
+    /// Builds the schedule from the settings; `None` when neither hours nor
+    /// days are set, which keeps the even pace.
+    pub fn from_settings(
+        hours: Option<&str>,
+        days: Option<&[String]>,
+        timezone: Option<&str>,
+    ) -> Result<Option<WorkingHours>> {
+        if hours.is_none() && days.is_none() {
+            return Ok(None);
+        }
+        let (start, end) = match hours {
+            Some(hours) => {
+                let (start, end) = hours
+                    .split_once('-')
+                    .ok_or_else(|| anyhow!("expected HH:MM-HH:MM"))
+                    .and_then(|(start, end)| Ok((parse_clock(start)?, parse_clock(end)?)))
+                    .with_context(|| format!("invalid working_hours `{hours}`"))?;
+                (start, end)
+            }
+            None => (0, 1440),
+        };
+        let days = match days {
+            Some(names) => {
+                let mut days = [false; 7];
+                for name in names {
+                    let day: Weekday = name
+                        .parse()
+                        .map_err(|_| anyhow!("invalid working_days entry `{name}`"))?;
+                    days[day.num_days_from_monday() as usize] = true;
+                }
+                days
+            }
+            None => [true; 7],
+        };
+        let zone =
+            match timezone {
+                Some(name) => Zone::Named(name.parse().map_err(|_| {
+                    anyhow!("unknown timezone `{name}` (expected e.g. Europe/Berlin)")
+                })?),
+                None => Zone::Local,
+            };
+        Ok(Some(WorkingHours {
+            start,
+            end,
+            days,
+            zone,
+        }))
+    }
+
+    /// Working minutes between `from` and `to`.
+    pub fn working_minutes(&self, from: DateTime<Utc>, to: DateTime<Utc>) -> f64 {
+        let end = if self.end <= self.start {
+            self.end + 1440
+        } else {
+            self.end
+        };
+        let at = |date: NaiveDate, minute: u32| {
+            let naive = date.and_hms_opt(0, 0, 0)? + Duration::minutes(i64::from(minute));
+            self.zone.utc_of(naive)
+        };
+
+        let mut total = 0.0;
+        // Start a day early for hours that run past midnight.
+        let mut date = self.zone.date_of(from) - Duration::days(1);
+        let last = self.zone.date_of(to);
+        while date <= last {
+            if self.days[date.weekday().num_days_from_monday() as usize] {
+                if let (Some(shift_start), Some(shift_end)) = (at(date, self.start), at(date, end))
+                {
+                    let (s, e) = (shift_start.max(from), shift_end.min(to));
+                    if e > s {
+                        total += (e - s).num_seconds() as f64 / 60.0;
+                    }
+                }
+            }
+            date += Duration::days(1);
+        }
+        total
+    }
+
+    /// Days with working time between `from` and `to`, counting today if
+    /// any of its working time is still ahead.
+    pub fn working_days(&self, from: DateTime<Utc>, to: DateTime<Utc>) -> u32 {
+        let midnight = |date: NaiveDate| self.zone.utc_of(date.and_hms_opt(0, 0, 0)?);
+        let mut count = 0;
+        let mut date = self.zone.date_of(from);
+        while date <= self.zone.date_of(to) {
+            let next = date + Duration::days(1);
+            if let (Some(start), Some(end)) = (midnight(date), midnight(next)) {
+                if self.working_minutes(start.max(from), end.min(to)) > 0.0 {
+                    count += 1;
+                }
+            }
+            date = next;
+        }
+        count
+    }
+}
+
+/// Recomputes each window's expected utilization (and so the pace marker
+/// and the reserve/deficit) so it only advances during working hours. A
+/// window with no working time in it keeps the even pace. Windows too young
+/// for an even pace get one once enough working time has passed.
+pub fn apply(hours: &WorkingHours, data: &mut ActiveData, now: DateTime<Utc>) {
+    for (_, length, window) in data.windows_by_key_mut() {
+        let Some(length) = length else {
+            continue;
+        };
+        let Some(resets_in) = window.resets_in_minutes else {
+            continue;
+        };
+        let reset = now + Duration::seconds((resets_in * 60.0) as i64);
+        let start = reset - Duration::minutes(length as i64);
+        let total = hours.working_minutes(start, reset);
+        if total <= 0.0 {
+            continue;
+        }
+        let expected = (hours.working_minutes(start, now) / total * 100.0).clamp(0.0, 100.0);
+        let actual = window.utilization.clamp(0.0, 100.0);
+        match &mut window.pace {
+            Some(pace) => {
+                pace.expected_percent = expected;
+                pace.delta_percent = actual - expected;
+            }
+            // The source leaves pace out while the even pace expects under
+            // 3%, e.g. the first hours of a session that started at 8:00
+            // with work from 9:00.
+            None if expected >= stats::MIN_EXPECTED_PERCENT
+                && resets_in > 0.0
+                && resets_in <= length =>
+            {
+                let elapsed = (length - resets_in) * 60.0;
+                window.pace = stats::linear_run_out(actual, elapsed, resets_in * 60.0).map(
+                    |(will_last_to_reset, eta_minutes)| PaceInfo {
+                        delta_percent: actual - expected,
+                        expected_percent: expected,
+                        will_last_to_reset,
+                        eta_minutes,
+                    },
+                );
+            }
+            None => {}
+        }
+    }
+}
@@ -0,0 +1,417 @@
+use std::fs;
+use std::path::Path;
+
+use ab_glyph::{FontRef, PxScale};
+use anyhow::Result;
+use image::{Rgba, RgbaImage};
+use imageproc::drawing::draw_text_mut;
+use serde::Serialize;
+
+use crate::render::{
+    draw_gradient_bar, draw_rounded_rect, draw_text_right, BG, DANGER_FILL, FONT_BOLD_BYTES,
+    FONT_BYTES, H, PANEL_BG, SEPARATOR, TEXT_DIM, TEXT_MUTED, TEXT_PRIMARY, W, WARN_FILL_LEFT,
+    WARN_FILL_RIGHT,
+};
+
+const COOL_LEFT: Rgba<u8> = Rgba([59, 130, 246, 255]);
+const COOL_RIGHT: Rgba<u8> = Rgba([6, 182, 212, 255]);
+const FAN_LEFT: Rgba<u8> = Rgba([34, 197, 94, 255]);
+const FAN_RIGHT: Rgba<u8> = Rgba([16, 185, 129, 255]);
+
+/// Default for the `temp_warn` setting, in °C.
+pub const DEFAULT_TEMP_WARN: f64 = 80.0;
+
+/// Default for the `temp_critical` setting, in °C.
+pub const DEFAULT_TEMP_CRITICAL: f64 = 95.0;
+
+/// Rows on the sensors screen.
+pub const MAX_SENSORS: usize = 5;
+
+/// Fan bars are full at the fan's reported maximum, or at this many RPM.
+const DEFAULT_FAN_MAX_RPM: f64 = 6000.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SensorKind {
+    /// In °C.
+    Temperature,
+    /// In RPM.
+    Fan,
+}
+
+/// One temperature or fan reading.
+#[derive(Debug, Clone, Serialize)]
+pub struct Sensor {
+    /// `chip/label`, e.g. `coretemp/Package id 0` or `thermal/acpitz`;
+    /// what the `sensors` setting matches against.
+    pub id: String,
+    pub label: String,
+    pub kind: SensorKind,
+    pub value: f64,
+    /// The chip's own limits: `temp*_max`/`temp*_crit` or a thermal zone's
+    /// passive/critical trip points; `fan*_max` for fans.
+    pub high: Option<f64>,
+    pub critical: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SensorInfo {
+    /// Hottest first, then fans; or in the order of the `sensors` setting.
+    pub sensors: Vec<Sensor>,
+    /// Average current and highest CPU frequency in MHz, where cpufreq is
+    /// available. A current frequency well below the highest under load
+    /// means the CPU is throttling.
+    pub cpu_mhz: Option<(f64, f64)>,
+}
+
+/// Temperatures at or above `warn` draw orange, at or above `critical` red.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Thresholds {
+    pub warn: f64,
+    pub critical: f64,
+}
+
+impl Default for Thresholds {
+    fn default() -> Self {
+        Thresholds {
+            warn: DEFAULT_TEMP_WARN,
+            critical: DEFAULT_TEMP_CRITICAL,
+        }
+    }
+}
+
+fn read_trimmed(path: &Path) -> Option<String> {
+    Some(fs::read_to_string(path).ok()?.trim().to_string())
+}
+
+fn read_number(path: &Path) -> Option<f64> {
+    read_trimmed(path)?.parse().ok()
+}
+
+/// Sensors under `/sys/class/hwmon` and `/sys/class/thermal`. Thermal zones
+/// that also show up as an hwmon chip of the same name are only listed once.
+/// Finds nothing on systems without sysfs (macOS).
+fn read_sensors(sys: &Path) -> Vec<Sensor> {
+    let mut sensors = Vec::new();
+    let mut chips = Vec::new();
+
+    for dir in sorted_entries(&sys.join("class/hwmon")) {
+        let Some(chip) = read_trimmed(&dir.join("name")) else {
+            continue;
+        };
+        for file in sorted_entries(&dir) {
+            let Some(name) = file.file_name().and_then(|n| n.to_str()) else {
+                continue;
+            };
+            let (kind, prefix) = if let Some(prefix) = name.strip_suffix("_input") {
+                match prefix {
+                    p if p.starts_with("temp") => (SensorKind::Temperature, p),
+                    p if p.starts_with("fan") => (SensorKind::Fan, p),
+                    _ => continue,
+                }
+            } else {
+                continue;
+            };
+            let Some(raw) = read_number(&file) else {
+                continue;
+            };
+            let label =
+                read_trimmed(&dir.join(format!("{prefix}_label"))).unwrap_or_else(
+                    || match prefix {
+                        // A chip's only temperature goes by the chip's name.
+                        "temp1" => chip.clone(),
+                        _ => prefix.to_string(),
+                    },
+                );
+            let sensor = match kind {
+                // Millidegrees.
+                SensorKind::Temperature => Sensor {
+                    id: format!("{chip}/{label}"),
+                    label,
+                    kind,
+                    value: raw / 1000.0,
+                    high: read_number(&dir.join(format!("{prefix}_max"))).map(|v| v / 1000.0),
+                    critical: read_number(&dir.join(format!("{prefix}_crit"))).map(|v| v / 1000.0),
+                },
+                SensorKind::Fan => Sensor {
+                    id: format!("{chip}/{label}"),
+                    label,
+                    kind,
+                    value: raw,
+                    high: read_number(&dir.join(format!("{prefix}_max"))),
+                    critical: None,
+                },
+            };
+            sensors.push(sensor);
+        }
+        chips.push(chip);
+    }
+
+    for dir in sorted_entries(&sys.join("class/thermal")) {
+        let Some(zone) = read_trimmed(&dir.join("type")) else {
+            continue;
+        };
+        if chips.contains(&zone) {
+            continue;
+        }
+        let Some(millidegrees) = read_number(&dir.join("temp")) else {
+            continue;
+        };
+        let trip = |wanted: &str| {
+            (0..16).find_map(|i| {
+                let kind = read_trimmed(&dir.join(format!("trip_point_{i}_type")))?;
+                (kind == wanted)
+                    .then(|| read_number(&dir.join(format!("trip_point_{i}_temp"))))
+                    .flatten()
+                    .map(|v| v / 1000.0)
+            })
+        };
+        sensors.push(Sensor {
+            id: format!("thermal/{zone}"),
+            label: zone.clone(),
+            kind: SensorKind::Temperature,
+            value: millidegrees / 1000.0,
+            high: trip("passive").or_else(|| trip("hot")),
+            critical: trip("critical"),
+        });
+    }
+    sensors
+}
+
+fn sorted_entries(dir: &Path) -> Vec<std::path::PathBuf> {
+    let mut entries: Vec<_> = fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .filter_map(|e| Some(e.ok()?.path()))
+        .collect();
+    entries.sort();
+    entries
+}
+
+/// Average `scaling_cur_freq` and highest `cpuinfo_max_freq` over all CPUs.
+fn read_cpu_mhz(sys: &Path) -> Option<(f64, f64)> {
+    let mut current = Vec::new();
+    let mut max: f64 = 0.0;
+    for dir in sorted_entries(&sys.join("devices/system/cpu")) {
+        let cpufreq = dir.join("cpufreq");
+        if let Some(khz) = read_number(&cpufreq.join("scaling_cur_freq")) {
+            current.push(khz / 1000.0);
+        }
+        if let Some(khz) = read_number(&cpufreq.join("cpuinfo_max_freq")) {
+            max = max.max(khz / 1000.0);
+        }
+    }
+    if current.is_empty() || max <= 0.0 {
+        return None;
+    }
+    Some((current.iter().sum::<f64>() / current.len() as f64, max))
+}
+
+/// Picks up to [`MAX_SENSORS`] sensors: for each entry of `wanted`, the
+/// first not yet picked whose id contains it (ignoring case); without
+/// `wanted`, the hottest temperatures and then the fans.
+fn select(mut sensors: Vec<Sensor>, wanted: Option<&[String]>) -> Vec<Sensor> {
+    match wanted {
+        Some(wanted) => {
+            let mut picked = Vec::new();
+            for pattern in wanted {
+                let pattern = pattern.to_lowercase();
+                if let Some(i) = sensors
+                    .iter()
+                    .position(|s| s.id.to_lowercase().contains(&pattern))
+                {
+                    picked.push(sensors.remove(i));
+                }
+            }
+            picked.truncate(MAX_SENSORS);
+            picked
+        }
+        None => {
+            sensors.sort_by(|a, b| {
+                (a.kind == SensorKind::Fan)
+                    .cmp(&(b.kind == SensorKind::Fan))
+                    .then(b.value.total_cmp(&a.value))
+            });
+            sensors.truncate(MAX_SENSORS);
+            sensors
+        }
+    }
+}
+
+/// Reads the sensors below `sys` (normally `/sys`) and picks the ones for
+/// the screen; see [`get_sensor_info`].
+pub fn read_sensor_info(sys: &Path, wanted: Option<&[String]>) -> SensorInfo {
+    SensorInfo {
+        sensors: select(read_sensors(sys), wanted),
+        cpu_mhz: read_cpu_mhz(sys),
+    }
+}
+
+/// The sensors listed in the `sensors` setting, or the hottest ones.
+pub fn get_sensor_info(wanted: Option<&[String]>) -> SensorInfo {
+    read_sensor_info(Path::new("/sys"), wanted)
+}
+
+/// Warn and critical levels for `sensor`: the configured thresholds where
+/// set, else the chip's own limits, else the defaults.
+fn limits(sensor: &Sensor, configured: (Option<f64>, Option<f64>)) -> Thresholds {
+    let defaults = Thresholds::default();
+    let critical = configured
+        .1
+        .or(sensor.critical)
+        .unwrap_or(defaults.critical);
+    let warn = configured
+        .0
+        .or(sensor.high)
+        .unwrap_or(defaults.warn)
+        .min(critical);
+    Thresholds { warn, critical }
+}
+
+/// CPU clock as "current / max" in one unit: GHz once the max reaches
+/// 1 GHz, MHz below that.
+pub fn format_clock(current_mhz: f64, max_mhz: f64) -> String {
+    if max_mhz >= 1000.0 {
+        format!("{:.1} / {:.1} GHz", current_mhz / 1000.0, max_mhz / 1000.0)
+    } else {
+        format!("{current_mhz:.0} / {max_mhz:.0} MHz")
+    }
+}
+
+/// Screen with up to [`MAX_SENSORS`] rows: temperatures with bars up to
+/// their critical level, orange from the warn level and red from the
+/// critical one, and fans in RPM. `temp_warn`/`temp_critical` override the
+/// chips' own limits when set.
+pub fn render_sensors(
+    info: &SensorInfo,
+    temp_warn: Option<f64>,
+    temp_critical: Option<f64>,
+    dither: bool,
+) -> Result<RgbaImage> {
+    let font = FontRef::try_from_slice(FONT_BYTES)?;
+    let font_bold = FontRef::try_from_slice(FONT_BOLD_BYTES)?;
+    let mut img = RgbaImage::from_pixel(W, H, BG);
+
+    let mx = 16i32;
+    let right_edge = W as i32 - mx;
+    let content_w = (right_edge - mx) as u32;
+
+    // Header
+    let header_y = 10;
+    draw_text_mut(
+        &mut img,
+        TEXT_PRIMARY,
+        mx,
+        header_y,
+        PxScale::from(17.0),
+        &font_bold,
+        "Sensors",
+    );
+    if let Some((current, max)) = info.cpu_mhz {
+        let freq_text = format_clock(current, max);
+        draw_text_right(
+            &mut img,
+            TEXT_DIM,
+            right_edge,
+            header_y + 1,
+            15.0,
+            &font,
+            &freq_text,
+        );
+    }
+    draw_rounded_rect(&mut img, mx, 33, content_w, 1, 0, SEPARATOR);
+
+    if info.sensors.is_empty() {
+        draw_text_mut(
+            &mut img,
+            TEXT_DIM,
+            58,
+            110,
+            PxScale::from(16.0),
+            &font,
+            "No sensors found",
+        );
+        return Ok(img);
+    }
+
+    let row_h = 36i32;
+    let gap = 3i32;
+    let start_y = 40;
+    for (i, sensor) in info.sensors.iter().take(MAX_SENSORS).enumerate() {
+        let y = start_y + i as i32 * (row_h + gap);
+        let text_x = mx + 4;
+        let inner_right = right_edge - 2;
+        draw_rounded_rect(
+            &mut img,
+            mx - 4,
+            y,
+            content_w + 8,
+            row_h as u32,
+            8,
+            PANEL_BG,
+        );
+
+        let (value_text, frac, colors, value_color) = match sensor.kind {
+            SensorKind::Temperature => {
+                let limits = limits(sensor, (temp_warn, temp_critical));
+                let (colors, value_color) = if sensor.value >= limits.critical {
+                    ((DANGER_FILL, DANGER_FILL), DANGER_FILL)
+                } else if sensor.value >= limits.warn {
+                    ((WARN_FILL_LEFT, WARN_FILL_RIGHT), WARN_FILL_RIGHT)
+                } else {
+                    ((COOL_LEFT, COOL_RIGHT), TEXT_PRIMARY)
+                };
+                (
+                    format!("{:.0}°C", sensor.value),
+                    sensor.value / limits.critical,
+                    colors,
+                    value_color,
+                )
+            }
+            SensorKind::Fan => (
+                format!("{:.0} RPM", sensor.value),
+                sensor.value / sensor.high.unwrap_or(DEFAULT_FAN_MAX_RPM),
+                (FAN_LEFT, FAN_RIGHT),
+                TEXT_PRIMARY,
+            ),
+        };
+
+        let label: String = if sensor.label.chars().count() > 18 {
+            sensor.label.chars().take(17).chain(['…']).collect()
+        } else {
+            sensor.label.clone()
+        };
+        draw_text_mut(
+            &mut img,
+            TEXT_MUTED,
+            text_x,
+            y + 5,
+            PxScale::from(13.0),
+            &font_bold,
+            &label,
+        );
+        draw_text_right(
+            &mut img,
+            value_color,
+            inner_right,
+            y + 2,
+            18.0,
+            &font_bold,
+            &value_text,
+        );
+        draw_gradient_bar(
+            &mut img,
+            text_x,
+            y + 25,
+            (inner_right - text_x) as u32,
+            5,
+            frac as f32,
+            colors.0,
+            colors.1,
+            2,
+            dither,
+        );
+    }
+
+    Ok(img)
+}
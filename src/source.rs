@@ -0,0 +1,163 @@
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::process::Command;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{bail, Context, Result};
+
+/// Default for the `source` setting.
+pub const DEFAULT_SOURCE: &str = "claude-code-stats";
+
+/// Produces a claude-code-stats widget payload (`{"status": ..., "data": ...}`)
+/// as JSON.
+pub trait UsageSource: Send + Sync {
+    fn fetch_json(&self) -> Result<String>;
+
+    /// Short description for logs.
+    fn describe(&self) -> String;
+}
+
+/// Collects usage in-process with the `claude-code-stats` crate.
+pub struct ClaudeCodeStats;
+
+impl UsageSource for ClaudeCodeStats {
+    fn fetch_json(&self) -> Result<String> {
+        // Not `collect_widget_payload_json`, which flattens failures into an
+        // error payload and loses what kind of error they were.
+        let payload = claude_code_stats::collect_widget_payload()?;
+        Ok(serde_json::to_string(&payload)?)
+    }
+
+    fn describe(&self) -> String {
+        DEFAULT_SOURCE.to_string()
+    }
+}
+
+/// Re-reads a JSON file on every fetch, e.g. one written by another tool.
+pub struct FileSource {
+    pub path: PathBuf,
+}
+
+impl UsageSource for FileSource {
+    fn fetch_json(&self) -> Result<String> {
+        fs::read_to_string(&self.path)
+            .with_context(|| format!("failed to read {}", self.path.display()))
+    }
+
+    fn describe(&self) -> String {
+        format!("file {}", self.path.display())
+    }
+}
+
+/// Reads the next JSON value from stdin on every fetch, so a producer can
+/// stream one payload per update.
+pub struct StdinSource;
+
+impl UsageSource for StdinSource {
+    fn fetch_json(&self) -> Result<String> {
+        let stdin = io::stdin().lock();
+        let value = serde_json::Deserializer::from_reader(stdin)
+            .into_iter::<serde_json::Value>()
+            .next()
+            .context("stdin closed before a payload arrived")?
+            .context("invalid JSON on stdin")?;
+        Ok(value.to_string())
+    }
+
+    fn describe(&self) -> String {
+        "stdin".to_string()
+    }
+}
+
+/// Runs a shell command and uses its stdout.
+pub struct CommandSource {
+    pub command: String,
+}
+
+impl UsageSource for CommandSource {
+    fn fetch_json(&self) -> Result<String> {
+        let output = Command::new("sh")
+            .args(["-c", &self.command])
+            .output()
+            .with_context(|| format!("failed to run `{}`", self.command))?;
+        if !output.status.success() {
+            bail!(
+                "`{}` exited with {}: {}",
+                self.command,
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        String::from_utf8(output.stdout).context("command output is not UTF-8")
+    }
+
+    fn describe(&self) -> String {
+        format!("command `{}`", self.command)
+    }
+}
+
+/// Fetches the payload from an HTTP endpoint, such as a local proxy.
+pub struct HttpSource {
+    pub url: String,
+}
+
+impl UsageSource for HttpSource {
+    fn fetch_json(&self) -> Result<String> {
+        let client = reqwest::blocking::Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()?;
+        client
+            .get(&self.url)
+            .send()
+            .and_then(|resp| resp.error_for_status())
+            .and_then(|resp| resp.text())
+            .with_context(|| format!("failed to fetch {}", self.url))
+    }
+
+    fn describe(&self) -> String {
+        self.url.clone()
+    }
+}
+
+/// Parses a source spec:
+///
+/// - `claude-code-stats`: the in-process collector (default)
+/// - `file:PATH`: a JSON file, re-read on every update
+/// - `stdin`: a stream of JSON payloads on stdin
+/// - `command:CMD`: the stdout of `sh -c CMD`
+/// - `http://...` or `https://...`: an HTTP endpoint
+pub fn parse(spec: &str) -> Result<Arc<dyn UsageSource>> {
+    let spec = spec.trim();
+    if spec == DEFAULT_SOURCE {
+        return Ok(Arc::new(ClaudeCodeStats));
+    }
+    if spec == "stdin" {
+        return Ok(Arc::new(StdinSource));
+    }
+    if let Some(path) = spec.strip_prefix("file:") {
+        if path.is_empty() {
+            bail!("source `file:` needs a path, e.g. file:/path/payload.json");
+        }
+        return Ok(Arc::new(FileSource {
+            path: PathBuf::from(path),
+        }));
+    }
+    if let Some(command) = spec.strip_prefix("command:") {
+        if command.trim().is_empty() {
+            bail!("source `command:` needs a command, e.g. command:my-script");
+        }
+        return Ok(Arc::new(CommandSource {
+            command: command.to_string(),
+        }));
+    }
+    if spec.starts_with("http://") || spec.starts_with("https://") {
+        return Ok(Arc::new(HttpSource {
+            url: spec.to_string(),
+        }));
+    }
+    bail!(
+        "unknown source `{spec}`; expected {DEFAULT_SOURCE}, stdin, file:PATH, command:CMD or an http(s) URL"
+    )
+}
@@ -1,29 +1,298 @@
-use anyhow::{Context, Result};
-use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io;
+
+use anyhow::{bail, Context, Result};
+use reqwest::StatusCode;
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::budget::Allowance;
+use crate::source::UsageSource;
 
 #[derive(Debug, Deserialize)]
 pub struct StatsPayload {
-    #[allow(dead_code)]
-    pub status: String,
+    pub status: UsageStatus,
+    pub title: Option<String>,
+    pub message: Option<String>,
     pub data: Option<ActiveData>,
 }
 
-#[derive(Debug, Deserialize)]
+/// The payload's `status` field.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(from = "String")]
+pub enum UsageStatus {
+    Active,
+    LoggedOut,
+    RateLimited,
+    NetworkError,
+    /// Any other status, including claude-code-stats' generic `error` when
+    /// it doesn't report a known HTTP status.
+    Unknown(String),
+}
+
+impl From<String> for UsageStatus {
+    fn from(s: String) -> Self {
+        match s.as_str() {
+            "active" => UsageStatus::Active,
+            "logged_out" | "unauthenticated" => UsageStatus::LoggedOut,
+            "rate_limited" => UsageStatus::RateLimited,
+            "network_error" | "offline" => UsageStatus::NetworkError,
+            _ => UsageStatus::Unknown(s),
+        }
+    }
+}
+
+impl fmt::Display for UsageStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UsageStatus::Active => f.write_str("active"),
+            UsageStatus::LoggedOut => f.write_str("logged out"),
+            UsageStatus::RateLimited => f.write_str("rate limited"),
+            UsageStatus::NetworkError => f.write_str("network error"),
+            UsageStatus::Unknown(raw) => write!(f, "status {raw}"),
+        }
+    }
+}
+
+impl UsageStatus {
+    /// Classifies a failed fetch by what actually went wrong: the HTTP status
+    /// of a rejected request, or a transport failure. Anything else is a
+    /// generic `error`.
+    pub fn from_error(err: &anyhow::Error) -> UsageStatus {
+        for cause in err.chain() {
+            if let Some(e) = cause.downcast_ref::<reqwest::Error>() {
+                if let Some(status) = e.status().and_then(UsageStatus::from_http_status) {
+                    return status;
+                }
+                if e.is_connect() || e.is_timeout() || e.is_request() {
+                    return UsageStatus::NetworkError;
+                }
+            }
+            if let Some(e) = cause.downcast_ref::<io::Error>() {
+                if is_network_io(e.kind()) {
+                    return UsageStatus::NetworkError;
+                }
+            }
+        }
+        // claude-code-stats turns HTTP failures into text, keeping the status
+        // in its `StatusCode` form ("401 Unauthorized").
+        err.chain()
+            .find_map(|cause| status_in_message(&cause.to_string()))
+            .unwrap_or_else(|| UsageStatus::Unknown("error".to_string()))
+    }
+
+    fn from_http_status(status: StatusCode) -> Option<UsageStatus> {
+        match status {
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => Some(UsageStatus::LoggedOut),
+            StatusCode::TOO_MANY_REQUESTS => Some(UsageStatus::RateLimited),
+            _ => None,
+        }
+    }
+}
+
+fn is_network_io(kind: io::ErrorKind) -> bool {
+    use io::ErrorKind::*;
+    matches!(
+        kind,
+        ConnectionRefused
+            | ConnectionReset
+            | ConnectionAborted
+            | NotConnected
+            | TimedOut
+            | HostUnreachable
+            | NetworkUnreachable
+            | NetworkDown
+    )
+}
+
+/// Finds an HTTP status written the way `StatusCode` displays it, e.g. the
+/// "429 Too Many Requests" in "usage API failed (429 Too Many Requests): ...".
+fn status_in_message(text: &str) -> Option<UsageStatus> {
+    [
+        StatusCode::UNAUTHORIZED,
+        StatusCode::FORBIDDEN,
+        StatusCode::TOO_MANY_REQUESTS,
+    ]
+    .into_iter()
+    .find(|status| text.contains(&status.to_string()))
+    .and_then(UsageStatus::from_http_status)
+}
+
+impl StatsPayload {
+    /// The status, with a generic `error` refined by the HTTP status its
+    /// message reports, if any.
+    pub fn effective_status(&self) -> UsageStatus {
+        if self.status == UsageStatus::Unknown("error".to_string()) {
+            if let Some(status) = self.message.as_deref().and_then(status_in_message) {
+                return status;
+            }
+        }
+        self.status.clone()
+    }
+}
+
+/// Length of the session (`five_hour`) window.
+pub const FIVE_HOUR_MINUTES: f64 = 5.0 * 60.0;
+
+/// Length of the weekly (`seven_day`) window.
+pub const SEVEN_DAY_MINUTES: f64 = 7.0 * 24.0 * 60.0;
+
+/// Result of one fetch: usage data, or why there is none.
+#[derive(Debug)]
+pub enum Stats {
+    Active(Box<ActiveData>),
+    Inactive {
+        status: UsageStatus,
+        message: Option<String>,
+    },
+}
+
+impl Stats {
+    /// The status screen for a fetch that failed outright.
+    pub fn from_error(err: &anyhow::Error) -> Stats {
+        Stats::Inactive {
+            status: UsageStatus::from_error(err),
+            message: Some(format!("{err:#}")),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct ActiveData {
     pub five_hour: Option<UsageWindow>,
     pub seven_day: Option<UsageWindow>,
     pub updated_at: Option<String>,
+    /// Any other windows in the payload (e.g. `seven_day_opus`), by key.
+    /// Entries that aren't usage windows (`extra_usage`, `cost_data`, nulls)
+    /// are dropped.
+    #[serde(flatten, deserialize_with = "extra_windows")]
+    pub extra: BTreeMap<String, ExtraWindow>,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+impl ActiveData {
+    /// Every window with its label, session and weekly first.
+    pub fn windows(&self) -> Vec<(&str, &UsageWindow)> {
+        let main = [("Session", &self.five_hour), ("Weekly", &self.seven_day)]
+            .into_iter()
+            .filter_map(|(label, w)| w.as_ref().map(|w| (label, w)));
+        let extra = self.extra.values().map(|e| (e.label.as_str(), &e.window));
+        main.chain(extra).collect()
+    }
+
+    /// Every window by payload key (`five_hour`, `seven_day_opus`, ...), in
+    /// the order of [`ActiveData::windows`].
+    pub fn windows_by_key(&self) -> Vec<(&str, &UsageWindow)> {
+        let main = [
+            ("five_hour", &self.five_hour),
+            ("seven_day", &self.seven_day),
+        ]
+        .into_iter()
+        .filter_map(|(key, w)| w.as_ref().map(|w| (key, w)));
+        let extra = self.extra.iter().map(|(key, e)| (key.as_str(), &e.window));
+        main.chain(extra).collect()
+    }
+
+    /// Like [`ActiveData::windows_by_key`], for updating the windows, with
+    /// each window's length in minutes when known.
+    pub fn windows_by_key_mut(&mut self) -> Vec<(&str, Option<f64>, &mut UsageWindow)> {
+        let main = [
+            ("five_hour", FIVE_HOUR_MINUTES, &mut self.five_hour),
+            ("seven_day", SEVEN_DAY_MINUTES, &mut self.seven_day),
+        ]
+        .into_iter()
+        .filter_map(|(key, length, w)| w.as_mut().map(|w| (key, Some(length), w)));
+        let extra = self
+            .extra
+            .iter_mut()
+            .map(|(key, e)| (key.as_str(), e.window_minutes, &mut e.window));
+        main.chain(extra).collect()
+    }
+}
+
+/// A usage window beyond the session and weekly ones.
+#[derive(Debug, Serialize, Clone)]
+pub struct ExtraWindow {
+    /// The payload's `label`, or one made from the key (`seven_day_opus` is
+    /// "Weekly Opus").
+    pub label: String,
+    /// The payload's `window_minutes`, or the length implied by a
+    /// `five_hour`/`seven_day` key prefix. Without it there is no pace.
+    pub window_minutes: Option<f64>,
+    #[serde(flatten)]
+    pub window: UsageWindow,
+}
+
+#[derive(Deserialize)]
+struct ExtraWindowFields {
+    label: Option<String>,
+    window_minutes: Option<f64>,
+    #[serde(flatten)]
+    window: UsageWindow,
+}
+
+/// Label and window length implied by a payload key.
+fn describe_key(key: &str) -> (String, Option<f64>) {
+    let (prefix, minutes, rest) = if let Some(rest) = key.strip_prefix("five_hour") {
+        ("Session", Some(FIVE_HOUR_MINUTES), rest)
+    } else if let Some(rest) = key.strip_prefix("seven_day") {
+        ("Weekly", Some(SEVEN_DAY_MINUTES), rest)
+    } else {
+        ("", None, key)
+    };
+    let words = rest.split('_').filter(|w| !w.is_empty()).map(|w| {
+        let mut chars = w.chars();
+        chars
+            .next()
+            .map(|c| c.to_uppercase().chain(chars).collect::<String>())
+            .unwrap_or_default()
+    });
+    let label = std::iter::once(prefix.to_string())
+        .filter(|p| !p.is_empty())
+        .chain(words)
+        .collect::<Vec<_>>()
+        .join(" ");
+    (label, minutes)
+}
+
+fn extra_windows<'de, D>(deserializer: D) -> Result<BTreeMap<String, ExtraWindow>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let fields = BTreeMap::<String, serde_json::Value>::deserialize(deserializer)?;
+    Ok(fields
+        .into_iter()
+        .filter_map(|(key, value)| {
+            let fields: ExtraWindowFields = serde_json::from_value(value).ok()?;
+            let (label, minutes) = describe_key(&key);
+            let window = ExtraWindow {
+                label: fields.label.unwrap_or(label),
+                window_minutes: fields.window_minutes.or(minutes),
+                window: fields.window,
+            };
+            Some((key, window))
+        })
+        .collect())
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct UsageWindow {
     pub utilization: f64,
     pub resets_in_minutes: Option<f64>,
     pub usage_level: String,
     pub pace: Option<PaceInfo>,
+    /// Filled in locally by [`crate::budget::apply`].
+    #[serde(default, skip_deserializing)]
+    pub allowance: Option<Allowance>,
+}
+
+impl UsageWindow {
+    /// At or past the limit (`danger` or `over`).
+    pub fn is_critical(&self) -> bool {
+        matches!(self.usage_level.as_str(), "danger" | "over")
+    }
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct PaceInfo {
     pub delta_percent: f64,
     pub expected_percent: f64,
@@ -31,9 +300,44 @@ pub struct PaceInfo {
     pub eta_minutes: Option<f64>,
 }
 
+/// Expected utilization below which a window is too young for a pace.
+pub const MIN_EXPECTED_PERCENT: f64 = 3.0;
+
+/// Whether usage at the average rate so far lasts until the reset, and if
+/// not, the minutes until it runs out. `None` before the window has started.
+pub(crate) fn linear_run_out(
+    actual: f64,
+    elapsed_seconds: f64,
+    time_left_seconds: f64,
+) -> Option<(bool, Option<f64>)> {
+    if elapsed_seconds > 0.0 && actual > 0.0 {
+        let rate = actual / elapsed_seconds;
+        if rate > 0.0 {
+            let remaining = (100.0 - actual).max(0.0);
+            let candidate = remaining / rate;
+            if candidate >= time_left_seconds {
+                Some((true, None))
+            } else {
+                Some((false, Some(candidate / 60.0)))
+            }
+        } else {
+            Some((true, None))
+        }
+    } else if elapsed_seconds > 0.0 {
+        Some((true, None))
+    } else {
+        None
+    }
+}
+
 /// Compute pace locally when the API doesn't provide it.
-/// Mirrors the logic in claude-code-stats/src/types.rs.
-fn compute_pace(utilization: f64, resets_in_minutes: f64, window_minutes: f64) -> Option<PaceInfo> {
+/// Mirrors the logic in claude-code-stats/src/types.rs; `tests/pace.rs`
+/// checks that the two agree.
+pub fn compute_pace(
+    utilization: f64,
+    resets_in_minutes: f64,
+    window_minutes: f64,
+) -> Option<PaceInfo> {
     if window_minutes <= 0.0 || resets_in_minutes <= 0.0 || resets_in_minutes > window_minutes {
         return None;
     }
@@ -45,30 +349,12 @@ fn compute_pace(utilization: f64, resets_in_minutes: f64, window_minutes: f64) -
     let actual = utilization.clamp(0.0, 100.0);
     let expected = ((elapsed / duration) * 100.0).clamp(0.0, 100.0);
 
-    if (elapsed == 0.0 && actual > 0.0) || expected < 3.0 {
+    if (elapsed == 0.0 && actual > 0.0) || expected < MIN_EXPECTED_PERCENT {
         return None;
     }
 
     let delta = actual - expected;
-
-    let (will_last_to_reset, eta_minutes) = if elapsed > 0.0 && actual > 0.0 {
-        let rate = actual / elapsed;
-        if rate > 0.0 {
-            let remaining = (100.0 - actual).max(0.0);
-            let candidate = remaining / rate;
-            if candidate >= time_left {
-                (true, None)
-            } else {
-                (false, Some(candidate / 60.0))
-            }
-        } else {
-            (true, None)
-        }
-    } else if elapsed > 0.0 {
-        (true, None)
-    } else {
-        return None;
-    };
+    let (will_last_to_reset, eta_minutes) = linear_run_out(actual, elapsed, time_left)?;
 
     Some(PaceInfo {
         delta_percent: delta,
@@ -88,22 +374,37 @@ fn ensure_pace(window: &mut UsageWindow, window_minutes: f64) {
     }
 }
 
-pub fn fetch_stats() -> Result<ActiveData> {
-    let payload_json = claude_code_stats::collect_widget_payload_json();
-    let payload: StatsPayload =
-        serde_json::from_str(&payload_json).context("failed to parse claude-code-stats payload")?;
+pub fn fetch_stats(source: &dyn UsageSource) -> Result<Stats> {
+    let payload_json = source.fetch_json()?;
+    let payload: StatsPayload = serde_json::from_str(&payload_json)
+        .with_context(|| format!("failed to parse payload from {}", source.describe()))?;
 
-    let mut data = payload
-        .data
-        .context("claude-code-stats returned non-active status")?;
+    let status = payload.effective_status();
+    let mut data = match (status, payload.data) {
+        (UsageStatus::Active, Some(data)) => data,
+        (UsageStatus::Active, None) => {
+            bail!("{} returned active status without data", source.describe())
+        }
+        (status, _) => {
+            return Ok(Stats::Inactive {
+                status,
+                message: payload.message,
+            })
+        }
+    };
 
     // Compute pace locally if not provided
     if let Some(w) = &mut data.five_hour {
-        ensure_pace(w, 300.0); // 5 hours
+        ensure_pace(w, FIVE_HOUR_MINUTES);
     }
     if let Some(w) = &mut data.seven_day {
-        ensure_pace(w, 10080.0); // 7 days
+        ensure_pace(w, SEVEN_DAY_MINUTES);
+    }
+    for extra in data.extra.values_mut() {
+        if let Some(minutes) = extra.window_minutes {
+            ensure_pace(&mut extra.window, minutes);
+        }
     }
 
-    Ok(data)
+    Ok(Stats::Active(Box::new(data)))
 }
@@ -0,0 +1,193 @@
+use std::fmt::Write;
+use std::io::Cursor;
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Local, Utc};
+use serde_json::{json, Value};
+
+use crate::http::{self, Response};
+use crate::telemetry::{Shared, Telemetry};
+
+fn timestamp(at: &DateTime<Utc>) -> Value {
+    Value::String(at.to_rfc3339())
+}
+
+/// The `/status` document.
+pub fn to_json(t: &Telemetry) -> Value {
+    let last_fetch = t.last_fetch.as_ref().map(|f| {
+        let (status, error) = match &f.outcome {
+            Ok(status) => (Some(status.as_str()), None),
+            Err(e) => (None, Some(e.as_str())),
+        };
+        json!({
+            "at": timestamp(&f.at),
+            "source": f.source,
+            "status": status,
+            "message": f.message,
+            "error": error,
+        })
+    });
+    let uploads: serde_json::Map<String, Value> = t
+        .uploads
+        .iter()
+        .map(|(page, at)| (page.clone(), json!({ "at": timestamp(at) })))
+        .collect();
+    let errors: Vec<Value> = t
+        .errors
+        .iter()
+        .map(|e| json!({ "at": timestamp(&e.at), "message": e.message }))
+        .collect();
+
+    json!({
+        "pid": std::process::id(),
+        "version": env!("CARGO_PKG_VERSION"),
+        "started_at": t.started_at.as_ref().map(timestamp),
+        "target": t.target,
+        "interval_seconds": t.interval_seconds,
+        "next_run_at": t.next_run.as_ref().map(timestamp),
+        "last_fetch": last_fetch,
+        "device_up": t.device_up,
+        "last_upload": {
+            "seconds": t.upload_seconds,
+            "bytes": t.upload_bytes,
+        },
+        "uploads": uploads,
+        "errors": errors,
+        "updates_total": t.updates_total,
+        "errors_total": t.errors_total,
+        "frames": t.frames.keys().map(|page| format!("/frames/{page}.png")).collect::<Vec<_>>(),
+    })
+}
+
+fn frame_png(t: &Telemetry, page: &str) -> Option<Vec<u8>> {
+    let frame = t.frames.get(page)?;
+    let mut png = Cursor::new(Vec::new());
+    frame.write_to(&mut png, image::ImageFormat::Png).ok()?;
+    Some(png.into_inner())
+}
+
+/// Serves `/status` (JSON) and `/frames/<page>.png` on `addr` from a
+/// background thread.
+pub fn serve(addr: &str, shared: Shared) -> Result<SocketAddr> {
+    http::serve(addr, move |path| {
+        let telemetry = shared.lock().unwrap();
+        if path == "/status" {
+            return Response::ok("application/json", to_json(&telemetry).to_string());
+        }
+        path.strip_prefix("/frames/")
+            .and_then(|file| file.strip_suffix(".png"))
+            .and_then(|page| frame_png(&telemetry, page))
+            .map_or_else(Response::not_found, |png| Response::ok("image/png", png))
+    })
+}
+
+/// Fetches `/status` from a running daemon.
+pub fn query(addr: &str) -> Result<Value> {
+    let client = reqwest::blocking::Client::builder()
+        .timeout(Duration::from_secs(5))
+        .build()?;
+    let resp = client
+        .get(format!("http://{addr}/status"))
+        .send()
+        .with_context(|| {
+            format!("no daemon answering on {addr}; is `geekmagic-stats --daemon` running?")
+        })?;
+    if !resp.status().is_success() {
+        bail!("{addr} answered {}", resp.status());
+    }
+    let body = resp.text().context("failed to read status response")?;
+    serde_json::from_str(&body).context("status response is not JSON")
+}
+
+fn local_time(value: &Value) -> Option<String> {
+    let at = DateTime::parse_from_rfc3339(value.as_str()?).ok()?;
+    Some(at.with_timezone(&Local).format("%H:%M:%S").to_string())
+}
+
+fn format_seconds(seconds: i64) -> String {
+    let s = seconds.max(0);
+    match (s / 3600, s % 3600 / 60, s % 60) {
+        (0, 0, s) => format!("{s}s"),
+        (0, m, s) => format!("{m}m {s}s"),
+        (h, m, _) => format!("{h}h {m}m"),
+    }
+}
+
+/// Human-readable summary of a `/status` document.
+pub fn summary(status: &Value, addr: &str) -> String {
+    let mut out = String::new();
+    let now = Utc::now();
+    let age = |value: &Value| {
+        let at = DateTime::parse_from_rfc3339(value.as_str()?).ok()?;
+        Some((now - at.with_timezone(&Utc)).num_seconds())
+    };
+
+    let uptime = age(&status["started_at"]).map(format_seconds);
+    let _ = writeln!(
+        out,
+        "Daemon: pid {}, up {}, pushing every {}s to {}",
+        status["pid"],
+        uptime.as_deref().unwrap_or("?"),
+        status["interval_seconds"],
+        status["target"].as_str().unwrap_or("?")
+    );
+    if let Some(next) = local_time(&status["next_run_at"]) {
+        let wait = age(&status["next_run_at"]).map(|a| format_seconds(-a));
+        let _ = writeln!(out, "Next run: {next} (in {})", wait.unwrap_or_default());
+    }
+
+    let fetch = &status["last_fetch"];
+    if fetch.is_object() {
+        let outcome = match (fetch["status"].as_str(), fetch["error"].as_str()) {
+            (Some(status), _) => status.to_string(),
+            (_, Some(error)) => format!("failed: {error}"),
+            _ => "?".to_string(),
+        };
+        let _ = writeln!(
+            out,
+            "Last fetch: {} from {}: {outcome}",
+            local_time(&fetch["at"]).unwrap_or_default(),
+            fetch["source"].as_str().unwrap_or("?")
+        );
+    } else {
+        let _ = writeln!(out, "Last fetch: none yet");
+    }
+
+    let device = match status["device_up"].as_bool() {
+        Some(true) => {
+            let upload = &status["last_upload"];
+            format!(
+                "reachable (last push {:.2}s, {} bytes)",
+                upload["seconds"].as_f64().unwrap_or_default(),
+                upload["bytes"]
+            )
+        }
+        Some(false) => "unreachable on the last push".to_string(),
+        None => "not contacted yet".to_string(),
+    };
+    let _ = writeln!(out, "Device: {device}");
+
+    if let Some(uploads) = status["uploads"].as_object().filter(|u| !u.is_empty()) {
+        let _ = writeln!(out, "Last uploads:");
+        for (page, upload) in uploads {
+            let at = local_time(&upload["at"]).unwrap_or_default();
+            let _ = writeln!(out, "  {page:<6} {at}");
+        }
+    }
+    if let Some(errors) = status["errors"].as_array().filter(|e| !e.is_empty()) {
+        let _ = writeln!(out, "Recent errors ({} total):", status["errors_total"]);
+        for error in errors.iter().rev().take(5) {
+            let at = local_time(&error["at"]).unwrap_or_default();
+            let _ = writeln!(out, "  {at} {}", error["message"].as_str().unwrap_or("?"));
+        }
+    }
+    if let Some(frames) = status["frames"].as_array().filter(|f| !f.is_empty()) {
+        let _ = writeln!(out, "Frames:");
+        for frame in frames.iter().filter_map(Value::as_str) {
+            let _ = writeln!(out, "  http://{addr}{frame}");
+        }
+    }
+    out
+}
@@ -0,0 +1,431 @@
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use std::thread;
+use std::time::Duration;
+
+use ab_glyph::{FontRef, PxScale};
+use anyhow::{anyhow, Context, Result};
+use image::{Rgba, RgbaImage};
+use imageproc::drawing::draw_text_mut;
+use serde::Serialize;
+
+use crate::disk_render::format_size;
+use crate::render::{
+    draw_gradient_bar, draw_rounded_rect, draw_text_right, HistoryGraph, BG, DANGER_FILL,
+    FONT_BOLD_BYTES, FONT_BYTES, H, PANEL_BG, SEPARATOR, TEXT_DIM, TEXT_MUTED, TEXT_PRIMARY, W,
+    WARN_FILL_LEFT, WARN_FILL_RIGHT,
+};
+
+const CPU_LEFT: Rgba<u8> = Rgba([59, 130, 246, 255]);
+const CPU_RIGHT: Rgba<u8> = Rgba([6, 182, 212, 255]);
+const MEM_LEFT: Rgba<u8> = Rgba([99, 102, 241, 255]);
+const MEM_RIGHT: Rgba<u8> = Rgba([139, 92, 246, 255]);
+const LOAD_LEFT: Rgba<u8> = Rgba([34, 197, 94, 255]);
+const LOAD_RIGHT: Rgba<u8> = Rgba([16, 185, 129, 255]);
+
+/// Samples kept for the history graphs, one per update: the last hour with
+/// `--daemon 60`, five hours with `--daemon 300`.
+pub const HISTORY_LEN: usize = 60;
+
+/// How long the first CPU reading waits for a second one, so a one-shot run
+/// shows current usage rather than the average since boot.
+const FIRST_CPU_INTERVAL: Duration = Duration::from_millis(250);
+
+/// One reading of CPU, memory and load.
+#[derive(Debug, Clone, Serialize)]
+pub struct SystemInfo {
+    pub hostname: Option<String>,
+    pub cpus: usize,
+    /// Busy share of all CPUs since the previous reading, 0-100.
+    pub cpu_percent: f64,
+    pub memory_total_bytes: u64,
+    /// Memory not available to new processes (total minus available).
+    pub memory_used_bytes: u64,
+    /// 1, 5 and 15 minute load averages.
+    pub load: [f64; 3],
+}
+
+impl SystemInfo {
+    pub fn memory_percent(&self) -> f64 {
+        if self.memory_total_bytes == 0 {
+            return 0.0;
+        }
+        self.memory_used_bytes as f64 / self.memory_total_bytes as f64 * 100.0
+    }
+}
+
+/// Cumulative CPU time from `/proc/stat`, in clock ticks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CpuTimes {
+    pub busy: u64,
+    pub total: u64,
+}
+
+impl CpuTimes {
+    /// Busy share between `earlier` and this reading, 0-100.
+    pub fn percent_since(self, earlier: CpuTimes) -> f64 {
+        let total = self.total.saturating_sub(earlier.total);
+        if total == 0 {
+            return 0.0;
+        }
+        self.busy.saturating_sub(earlier.busy) as f64 / total as f64 * 100.0
+    }
+}
+
+/// Takes readings and keeps the last [`HISTORY_LEN`] of them. Reads
+/// `/proc` on Linux and falls back to `sysctl`, `vm_stat` and `ps` on macOS.
+#[derive(Debug, Default)]
+pub struct SystemMonitor {
+    last_cpu: Option<CpuTimes>,
+    history: Vec<SystemInfo>,
+}
+
+impl SystemMonitor {
+    pub fn new() -> SystemMonitor {
+        SystemMonitor::default()
+    }
+
+    /// Takes a reading and adds it to the history.
+    pub fn sample(&mut self) -> Result<SystemInfo> {
+        let info = if Path::new("/proc/stat").exists() {
+            self.sample_proc()?
+        } else {
+            sample_macos()?
+        };
+        self.history.push(info.clone());
+        let excess = self.history.len().saturating_sub(HISTORY_LEN);
+        self.history.drain(..excess);
+        Ok(info)
+    }
+
+    /// Readings oldest first, ending with the latest.
+    pub fn history(&self) -> &[SystemInfo] {
+        &self.history
+    }
+
+    fn sample_proc(&mut self) -> Result<SystemInfo> {
+        let read =
+            |path: &str| fs::read_to_string(path).with_context(|| format!("failed to read {path}"));
+
+        let (mut cpu, cpus) = parse_proc_stat(&read("/proc/stat")?)?;
+        let earlier = match self.last_cpu {
+            Some(earlier) => earlier,
+            None => {
+                thread::sleep(FIRST_CPU_INTERVAL);
+                let earlier = cpu;
+                cpu = parse_proc_stat(&read("/proc/stat")?)?.0;
+                earlier
+            }
+        };
+        self.last_cpu = Some(cpu);
+
+        let (memory_total_bytes, memory_used_bytes) = parse_meminfo(&read("/proc/meminfo")?)?;
+        Ok(SystemInfo {
+            hostname: fs::read_to_string("/proc/sys/kernel/hostname")
+                .ok()
+                .map(|h| h.trim().to_string())
+                .filter(|h| !h.is_empty()),
+            cpus,
+            cpu_percent: cpu.percent_since(earlier),
+            memory_total_bytes,
+            memory_used_bytes,
+            load: parse_loadavg(&read("/proc/loadavg")?)?,
+        })
+    }
+}
+
+/// Aggregate CPU times and the number of CPUs from `/proc/stat`.
+pub fn parse_proc_stat(text: &str) -> Result<(CpuTimes, usize)> {
+    let line = text
+        .lines()
+        .find(|l| l.starts_with("cpu "))
+        .ok_or_else(|| anyhow!("no cpu line in /proc/stat"))?;
+    // user nice system idle iowait irq softirq steal; guest time is already
+    // counted in user.
+    let fields: Vec<u64> = line
+        .split_whitespace()
+        .skip(1)
+        .take(8)
+        .map(str::parse)
+        .collect::<Result<_, _>>()
+        .context("malformed cpu line in /proc/stat")?;
+    let total: u64 = fields.iter().sum();
+    let idle = fields.get(3).copied().unwrap_or(0) + fields.get(4).copied().unwrap_or(0);
+    let cpus = text
+        .lines()
+        .filter(|l| {
+            l.strip_prefix("cpu")
+                .is_some_and(|rest| rest.starts_with(|c: char| c.is_ascii_digit()))
+        })
+        .count();
+    Ok((
+        CpuTimes {
+            busy: total.saturating_sub(idle),
+            total,
+        },
+        cpus.max(1),
+    ))
+}
+
+/// Total and used memory in bytes from `/proc/meminfo`.
+pub fn parse_meminfo(text: &str) -> Result<(u64, u64)> {
+    let kb = |key: &str| {
+        text.lines()
+            .find_map(|l| l.strip_prefix(key)?.strip_prefix(':'))
+            .and_then(|v| v.split_whitespace().next()?.parse::<u64>().ok())
+            .map(|v| v * 1024)
+    };
+    let total = kb("MemTotal").context("no MemTotal in /proc/meminfo")?;
+    // Kernels before 3.14 have no MemAvailable.
+    let available = kb("MemAvailable")
+        .or_else(|| Some(kb("MemFree")? + kb("Buffers").unwrap_or(0) + kb("Cached").unwrap_or(0)))
+        .context("no MemAvailable in /proc/meminfo")?;
+    Ok((total, total.saturating_sub(available)))
+}
+
+/// Load averages from `/proc/loadavg` or `sysctl -n vm.loadavg`
+/// (`{ 1.20 1.05 0.98 }`).
+pub fn parse_loadavg(text: &str) -> Result<[f64; 3]> {
+    let values: Vec<f64> = text
+        .split_whitespace()
+        .filter(|v| *v != "{")
+        .take(3)
+        .map(str::parse)
+        .collect::<Result<_, _>>()
+        .with_context(|| format!("malformed load average `{}`", text.trim()))?;
+    values
+        .try_into()
+        .map_err(|_| anyhow!("malformed load average `{}`", text.trim()))
+}
+
+fn run(program: &str, args: &[&str]) -> Result<String> {
+    let output = Command::new(program)
+        .args(args)
+        .output()
+        .with_context(|| format!("failed to run {program}"))?;
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+fn sysctl(name: &str) -> Result<String> {
+    Ok(run("sysctl", &["-n", name])?.trim().to_string())
+}
+
+fn sample_macos() -> Result<SystemInfo> {
+    let cpus: usize = sysctl("hw.ncpu")?.parse().context("unexpected hw.ncpu")?;
+    let cpus = cpus.max(1);
+    // ps reports each process' decaying CPU average, per core.
+    let cpu_total: f64 = run("ps", &["-A", "-o", "%cpu="])?
+        .lines()
+        .filter_map(|l| l.trim().parse::<f64>().ok())
+        .sum();
+
+    let memory_total_bytes: u64 = sysctl("hw.memsize")?
+        .parse()
+        .context("unexpected hw.memsize")?;
+    let vm_stat = run("vm_stat", &[])?;
+    let page_size = vm_stat
+        .split("page size of ")
+        .nth(1)
+        .and_then(|rest| rest.split_whitespace().next()?.parse::<u64>().ok())
+        .unwrap_or(4096);
+    let pages = |key: &str| {
+        vm_stat
+            .lines()
+            .find_map(|l| l.strip_prefix(key)?.strip_prefix(':'))
+            .and_then(|v| v.trim().trim_end_matches('.').parse::<u64>().ok())
+            .unwrap_or(0)
+    };
+    let available =
+        (pages("Pages free") + pages("Pages inactive") + pages("Pages speculative")) * page_size;
+
+    Ok(SystemInfo {
+        hostname: run("hostname", &["-s"])
+            .ok()
+            .map(|h| h.trim().to_string())
+            .filter(|h| !h.is_empty()),
+        cpus,
+        cpu_percent: (cpu_total / cpus as f64).clamp(0.0, 100.0),
+        memory_total_bytes,
+        memory_used_bytes: memory_total_bytes.saturating_sub(available),
+        load: parse_loadavg(&sysctl("vm.loadavg")?)?,
+    })
+}
+
+/// Gauge colors for a row: its own while below 75% of the scale, then warn
+/// and danger.
+fn level_colors(frac: f64, left: Rgba<u8>, right: Rgba<u8>) -> (Rgba<u8>, Rgba<u8>) {
+    if frac >= 0.9 {
+        (DANGER_FILL, DANGER_FILL)
+    } else if frac >= 0.75 {
+        (WARN_FILL_LEFT, WARN_FILL_RIGHT)
+    } else {
+        (left, right)
+    }
+}
+
+struct Row<'a> {
+    label: &'a str,
+    detail: String,
+    value: String,
+    /// Gauge fill, 0-1.
+    frac: f64,
+    history: Vec<f64>,
+    history_max: f64,
+    colors: (Rgba<u8>, Rgba<u8>),
+}
+
+/// Screen with CPU, memory and load: the latest reading as gauges, and
+/// graphs of `history` (oldest first, ending with `info`).
+pub fn render_system(info: &SystemInfo, history: &[SystemInfo], dither: bool) -> Result<RgbaImage> {
+    let font = FontRef::try_from_slice(FONT_BYTES)?;
+    let font_bold = FontRef::try_from_slice(FONT_BOLD_BYTES)?;
+    let mut img = RgbaImage::from_pixel(W, H, BG);
+
+    let mx = 16i32;
+    let right_edge = W as i32 - mx;
+    let content_w = (right_edge - mx) as u32;
+
+    // Header
+    let header_y = 10;
+    draw_text_mut(
+        &mut img,
+        TEXT_PRIMARY,
+        mx,
+        header_y,
+        PxScale::from(17.0),
+        &font_bold,
+        "System",
+    );
+    if let Some(hostname) = &info.hostname {
+        let hostname: String = if hostname.chars().count() > 16 {
+            hostname.chars().take(15).chain(['…']).collect()
+        } else {
+            hostname.clone()
+        };
+        draw_text_right(
+            &mut img,
+            TEXT_DIM,
+            right_edge,
+            header_y + 1,
+            15.0,
+            &font,
+            &hostname,
+        );
+    }
+    draw_rounded_rect(&mut img, mx, 33, content_w, 1, 0, SEPARATOR);
+
+    let cores = info.cpus as f64;
+    let load_max = history.iter().map(|s| s.load[0]).fold(cores, f64::max);
+    let plural = if info.cpus == 1 { "" } else { "s" };
+    let rows = [
+        Row {
+            label: "CPU",
+            detail: format!("{} core{plural}", info.cpus),
+            value: format!("{}%", info.cpu_percent.round() as i32),
+            frac: info.cpu_percent / 100.0,
+            history: history.iter().map(|s| s.cpu_percent).collect(),
+            history_max: 100.0,
+            colors: (CPU_LEFT, CPU_RIGHT),
+        },
+        Row {
+            label: "Memory",
+            detail: format!(
+                "{} / {}",
+                format_size(info.memory_used_bytes),
+                format_size(info.memory_total_bytes)
+            ),
+            value: format!("{}%", info.memory_percent().round() as i32),
+            frac: info.memory_percent() / 100.0,
+            history: history.iter().map(SystemInfo::memory_percent).collect(),
+            history_max: 100.0,
+            colors: (MEM_LEFT, MEM_RIGHT),
+        },
+        Row {
+            label: "Load",
+            detail: format!("{:.2} · {:.2}", info.load[1], info.load[2]),
+            value: format!("{:.2}", info.load[0]),
+            frac: info.load[0] / cores,
+            history: history.iter().map(|s| s.load[0]).collect(),
+            history_max: load_max,
+            colors: (LOAD_LEFT, LOAD_RIGHT),
+        },
+    ];
+
+    let row_h = 62i32;
+    let gap = 4i32;
+    let start_y = 40;
+    let left_w = 96i32;
+    let graph_x = mx + left_w + 10;
+    let graph_w = (right_edge - 4 - graph_x) as u32;
+
+    for (i, row) in rows.iter().enumerate() {
+        let y = start_y + i as i32 * (row_h + gap);
+        draw_rounded_rect(
+            &mut img,
+            mx - 4,
+            y - 2,
+            content_w + 8,
+            row_h as u32,
+            10,
+            PANEL_BG,
+        );
+
+        let text_x = mx + 4;
+        draw_text_mut(
+            &mut img,
+            TEXT_MUTED,
+            text_x,
+            y + 2,
+            PxScale::from(13.0),
+            &font_bold,
+            row.label,
+        );
+        draw_text_mut(
+            &mut img,
+            TEXT_PRIMARY,
+            text_x,
+            y + 15,
+            PxScale::from(24.0),
+            &font_bold,
+            &row.value,
+        );
+
+        let (left, right) = level_colors(row.frac, row.colors.0, row.colors.1);
+        draw_gradient_bar(
+            &mut img,
+            text_x,
+            y + 42,
+            (left_w - 4) as u32,
+            5,
+            row.frac as f32,
+            left,
+            right,
+            2,
+            dither,
+        );
+        draw_text_mut(
+            &mut img,
+            TEXT_DIM,
+            text_x,
+            y + 48,
+            PxScale::from(10.0),
+            &font,
+            &row.detail,
+        );
+
+        HistoryGraph::new(
+            &mut img,
+            graph_x,
+            y + 3,
+            graph_w,
+            (row_h - 10) as u32,
+            HISTORY_LEN,
+            row.history_max,
+            dither,
+        )
+        .fill_area(&mut img, &row.history, right);
+    }
+
+    Ok(img)
+}
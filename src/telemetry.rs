@@ -0,0 +1,85 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::{Arc, Mutex};
+
+use chrono::{DateTime, Utc};
+use image::RgbaImage;
+
+use crate::disk_render::DiskInfo;
+use crate::network_render::NetworkInfo;
+use crate::sensors_render::SensorInfo;
+use crate::stats::ActiveData;
+use crate::system_render::SystemInfo;
+
+/// Recent errors kept for the status API.
+const MAX_ERRORS: usize = 20;
+
+/// Outcome of the last stats fetch.
+#[derive(Debug, Clone)]
+pub struct FetchRecord {
+    pub at: DateTime<Utc>,
+    pub source: String,
+    /// Payload status (`active`, `logged out`, ...) or the error message.
+    pub outcome: Result<String, String>,
+    /// The source's explanation when the status isn't `active`.
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ErrorRecord {
+    pub at: DateTime<Utc>,
+    pub message: String,
+}
+
+/// What the running daemon has collected and done, shared with its local
+/// endpoints (`/metrics`, `/status`).
+#[derive(Debug, Default)]
+pub struct Telemetry {
+    pub started_at: Option<DateTime<Utc>>,
+    /// Device host, or the terminal preview description.
+    pub target: String,
+    pub interval_seconds: u64,
+    pub next_run: Option<DateTime<Utc>>,
+    pub last_fetch: Option<FetchRecord>,
+    /// Usage from the last active payload; cleared when the source reports
+    /// no data.
+    pub usage: Option<ActiveData>,
+    pub disk: Option<DiskInfo>,
+    pub system: Option<SystemInfo>,
+    pub network: Option<NetworkInfo>,
+    pub sensors: Option<SensorInfo>,
+    /// Whether the last push to the device succeeded.
+    pub device_up: Option<bool>,
+    pub upload_seconds: Option<f64>,
+    pub upload_bytes: Option<usize>,
+    /// Pages of the last successful upload, in album order.
+    pub upload_pages: Vec<String>,
+    /// Last successful upload time per page (`stats`, `disk`, `system`, `alert`).
+    pub uploads: BTreeMap<String, DateTime<Utc>>,
+    /// Most recently rendered frame per page.
+    pub frames: BTreeMap<String, RgbaImage>,
+    pub errors: VecDeque<ErrorRecord>,
+    /// Unix time of the last successful update.
+    pub last_success: Option<i64>,
+    pub updates_total: u64,
+    pub errors_total: u64,
+}
+
+pub type Shared = Arc<Mutex<Telemetry>>;
+
+impl Telemetry {
+    pub fn record_error(&mut self, message: String) {
+        self.errors_total += 1;
+        if self.errors.len() == MAX_ERRORS {
+            self.errors.pop_front();
+        }
+        self.errors.push_back(ErrorRecord {
+            at: Utc::now(),
+            message,
+        });
+    }
+
+    pub fn record_success(&mut self) {
+        self.updates_total += 1;
+        self.last_success = Some(Utc::now().timestamp());
+    }
+}
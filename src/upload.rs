@@ -1,22 +1,31 @@
 use std::io::Cursor;
+use std::time::Instant;
 
 use anyhow::{Context, Result};
 use image::RgbaImage;
+use log::{debug, trace};
 use reqwest::blocking::multipart;
 
-fn encode_jpeg(img: &RgbaImage) -> Result<Vec<u8>> {
+pub fn encode_jpeg(img: &RgbaImage) -> Result<Vec<u8>> {
     let rgb = image::DynamicImage::ImageRgba8(img.clone()).into_rgb8();
     let mut jpeg_buf = Cursor::new(Vec::new());
     rgb.write_to(&mut jpeg_buf, image::ImageFormat::Jpeg)?;
     Ok(jpeg_buf.into_inner())
 }
 
+/// Encodes and uploads one page. Returns the JPEG size in bytes.
 fn upload_file(
     client: &reqwest::blocking::Client,
     base: &str,
     filename: &str,
-    jpeg_bytes: Vec<u8>,
-) -> Result<()> {
+    img: &RgbaImage,
+) -> Result<usize> {
+    let started = Instant::now();
+    let jpeg_bytes = encode_jpeg(img)?;
+    let encode_ms = started.elapsed().as_millis() as u64;
+    let size = jpeg_bytes.len();
+
+    let started = Instant::now();
     let part = multipart::Part::bytes(jpeg_bytes)
         .file_name(filename.to_string())
         .mime_str("image/jpeg")?;
@@ -40,7 +49,14 @@ fn upload_file(
             }
         }
     }
-    Ok(())
+    debug!(
+        file = filename,
+        bytes = size,
+        encode_ms,
+        upload_ms = started.elapsed().as_millis() as u64;
+        "Uploaded"
+    );
+    Ok(size)
 }
 
 fn make_client() -> Result<reqwest::blocking::Client> {
@@ -49,11 +65,12 @@ fn make_client() -> Result<reqwest::blocking::Client> {
         .build()?)
 }
 
-pub fn upload_and_display(host: &str, img: &RgbaImage) -> Result<()> {
+/// Uploads `img` and shows it. Returns the JPEG size in bytes.
+pub fn upload_and_display(host: &str, img: &RgbaImage) -> Result<usize> {
     let base = format!("http://{host}");
     let client = make_client()?;
 
-    upload_file(&client, &base, "stats.jpg", encode_jpeg(img)?)?;
+    let size = upload_file(&client, &base, "stats.jpg", img)?;
 
     client
         .get(format!("{base}/set?theme=3"))
@@ -64,10 +81,12 @@ pub fn upload_and_display(host: &str, img: &RgbaImage) -> Result<()> {
         .send()
         .context("failed to set image")?;
 
-    Ok(())
+    Ok(size)
 }
 
-pub fn upload_album(host: &str, images: &[(&str, &RgbaImage)]) -> Result<()> {
+/// Replaces the album with `images` and starts the slideshow. Returns the
+/// total JPEG size in bytes.
+pub fn upload_album(host: &str, images: &[(&str, &RgbaImage)]) -> Result<usize> {
     let base = format!("http://{host}");
     let client = make_client()?;
 
@@ -77,12 +96,14 @@ pub fn upload_album(host: &str, images: &[(&str, &RgbaImage)]) -> Result<()> {
     for line in body.lines() {
         let name = line.trim();
         if !name.is_empty() && name.ends_with(".jpg") {
+            trace!(file = name; "Deleting old album image");
             let _ = client.get(format!("{base}/del?path=/image//{name}")).send();
         }
     }
 
+    let mut size = 0;
     for (filename, img) in images {
-        upload_file(&client, &base, filename, encode_jpeg(img)?)?;
+        size += upload_file(&client, &base, filename, img)?;
     }
 
     client
@@ -102,5 +123,104 @@ pub fn upload_album(host: &str, images: &[(&str, &RgbaImage)]) -> Result<()> {
         .send()
         .context("failed to enable autoplay")?;
 
+    Ok(size)
+}
+
+/// Album slideshow settings, saved before the alert page takes over so they
+/// can be put back afterwards.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AlbumSettings {
+    pub autoplay: bool,
+    /// Seconds per image.
+    pub interval: u64,
+}
+
+impl Default for AlbumSettings {
+    /// What `upload_album` configures.
+    fn default() -> Self {
+        AlbumSettings {
+            autoplay: true,
+            interval: 10,
+        }
+    }
+}
+
+/// Reads the current slideshow settings from `/album.json`.
+pub fn album_settings(host: &str) -> Result<AlbumSettings> {
+    let body = make_client()?
+        .get(format!("http://{host}/album.json"))
+        .send()
+        .and_then(|resp| resp.text())
+        .context("failed to read album settings")?;
+    let json: serde_json::Value =
+        serde_json::from_str(&body).context("album.json is not valid JSON")?;
+    // The firmware reports numbers either bare or as strings.
+    let number = |key: &str| match &json[key] {
+        serde_json::Value::Number(n) => n.as_u64(),
+        serde_json::Value::String(s) => s.trim().parse().ok(),
+        serde_json::Value::Bool(b) => Some(u64::from(*b)),
+        _ => None,
+    };
+    let defaults = AlbumSettings::default();
+    Ok(AlbumSettings {
+        autoplay: number("autoplay").map_or(defaults.autoplay, |v| v != 0),
+        interval: number("i_i").unwrap_or(defaults.interval),
+    })
+}
+
+/// Shows `img` as a fixed page with the slideshow stopped, so it stays on
+/// screen instead of cycling away. Returns the JPEG size in bytes.
+pub fn show_alert(host: &str, img: &RgbaImage) -> Result<usize> {
+    let base = format!("http://{host}");
+    let client = make_client()?;
+
+    let size = upload_file(&client, &base, "alert.jpg", img)?;
+
+    client
+        .get(format!("{base}/set?autoplay=0"))
+        .send()
+        .context("failed to disable autoplay")?;
+    client
+        .get(format!("{base}/set?theme=3"))
+        .send()
+        .context("failed to set theme")?;
+    client
+        .get(format!("{base}/set?img=/image//alert.jpg"))
+        .send()
+        .context("failed to set image")?;
+
+    Ok(size)
+}
+
+/// Removes the alert page and puts back the slideshow settings saved by
+/// [`album_settings`].
+pub fn restore_album(host: &str, settings: AlbumSettings) -> Result<()> {
+    let base = format!("http://{host}");
+    let client = make_client()?;
+
+    let _ = client
+        .get(format!("{base}/del?path=/image//alert.jpg"))
+        .send();
+    client
+        .get(format!(
+            "{base}/set?i_i={}&autoplay={}",
+            settings.interval,
+            u8::from(settings.autoplay)
+        ))
+        .send()
+        .context("failed to restore autoplay")?;
+
+    Ok(())
+}
+
+pub fn set_brightness(host: &str, brightness: u8) -> Result<()> {
+    let base = format!("http://{host}");
+    let client = make_client()?;
+
+    client
+        .get(format!("{base}/set?brt={}", brightness.min(100)))
+        .send()
+        .context("failed to set brightness")?;
+
     Ok(())
 }
@@ -0,0 +1,249 @@
+//! Tests for alert rules: when they fire, when they re-arm, which windows
+//! they cover and how they're validated.
+
+use geekmagic_common::alert::{self, AlertRule, Alerter};
+use geekmagic_common::stats::ActiveData;
+use serde_json::{json, Value};
+
+fn window(utilization: f64, level: &str, eta: Option<f64>) -> Value {
+    json!({
+        "utilization": utilization,
+        "resets_in_minutes": 120.0,
+        "usage_level": level,
+        "pace": {
+            "delta_percent": 0.0,
+            "expected_percent": 50.0,
+            "will_last_to_reset": eta.is_none(),
+            "eta_minutes": eta,
+        },
+    })
+}
+
+fn session(utilization: f64) -> ActiveData {
+    serde_json::from_value(json!({ "five_hour": window(utilization, "normal", None) })).unwrap()
+}
+
+fn desktop(rule: AlertRule) -> AlertRule {
+    AlertRule {
+        desktop: Some(true),
+        ..rule
+    }
+}
+
+/// Feeds `readings` in turn and returns, per reading, the windows that fired.
+fn fired(alerter: &mut Alerter, readings: &[ActiveData]) -> Vec<Vec<String>> {
+    readings
+        .iter()
+        .map(|data| {
+            alerter
+                .check(data)
+                .into_iter()
+                .map(|alert| alert.window)
+                .collect()
+        })
+        .collect()
+}
+
+#[test]
+fn utilization_fires_once_and_rearms_below_the_margin() {
+    let mut alerter = Alerter::new(vec![desktop(AlertRule {
+        utilization: Some(80.0),
+        ..AlertRule::default()
+    })]);
+    let readings: Vec<_> = [70.0, 80.0, 85.0, 77.0, 80.0, 74.9, 79.0, 81.0]
+        .into_iter()
+        .map(session)
+        .collect();
+    let got: Vec<bool> = fired(&mut alerter, &readings)
+        .iter()
+        .map(|f| !f.is_empty())
+        .collect();
+    assert_eq!(got, [false, true, false, false, false, false, false, true]);
+}
+
+#[test]
+fn hysteresis_sets_the_clear_threshold() {
+    let mut alerter = Alerter::new(vec![desktop(AlertRule {
+        utilization: Some(80.0),
+        hysteresis: Some(0.0),
+        ..AlertRule::default()
+    })]);
+    let readings: Vec<_> = [80.0, 79.5, 80.0].into_iter().map(session).collect();
+    let got: Vec<usize> = fired(&mut alerter, &readings)
+        .iter()
+        .map(Vec::len)
+        .collect();
+    assert_eq!(got, [1, 0, 1]);
+}
+
+#[test]
+fn level_and_eta_rules_rearm_when_the_condition_clears() {
+    let reading = |level: &str, eta: Option<f64>| -> ActiveData {
+        serde_json::from_value(json!({ "five_hour": window(60.0, level, eta) })).unwrap()
+    };
+    let mut alerter = Alerter::new(vec![
+        desktop(AlertRule {
+            level: Some("danger".into()),
+            ..AlertRule::default()
+        }),
+        desktop(AlertRule {
+            eta_below: Some(60.0),
+            ..AlertRule::default()
+        }),
+    ]);
+    let readings = [
+        reading("warn", Some(90.0)),
+        reading("danger", Some(45.0)),
+        reading("over", Some(30.0)),
+        reading("warn", None),
+        reading("danger", Some(59.0)),
+    ];
+    let got: Vec<usize> = fired(&mut alerter, &readings)
+        .iter()
+        .map(Vec::len)
+        .collect();
+    assert_eq!(got, [0, 2, 0, 0, 2]);
+}
+
+#[test]
+fn rules_cover_every_window_unless_one_is_named() {
+    let data = |session: f64, weekly: f64, opus: f64| -> ActiveData {
+        serde_json::from_value(json!({
+            "five_hour": window(session, "normal", None),
+            "seven_day": window(weekly, "normal", None),
+            "seven_day_opus": window(opus, "normal", None),
+        }))
+        .unwrap()
+    };
+    let over_80 = |window: Option<&str>| {
+        desktop(AlertRule {
+            window: window.map(str::to_string),
+            utilization: Some(80.0),
+            ..AlertRule::default()
+        })
+    };
+    let mut alerter = Alerter::new(vec![over_80(None), over_80(Some("weekly"))]);
+    let readings = [
+        data(85.0, 90.0, 95.0),
+        // Only the session re-arms and fires again.
+        data(10.0, 90.0, 95.0),
+        data(85.0, 90.0, 95.0),
+    ];
+    assert_eq!(
+        fired(&mut alerter, &readings),
+        [
+            vec!["Session", "Weekly", "Weekly Opus", "Weekly"],
+            vec![],
+            vec!["Session"],
+        ]
+    );
+}
+
+#[test]
+fn alerts_describe_the_window() {
+    let data: ActiveData = serde_json::from_value(json!({
+        "five_hour": window(81.6, "danger", Some(90.0)),
+        "seven_day": window(40.0, "normal", None),
+    }))
+    .unwrap();
+    let cases = [
+        (
+            AlertRule {
+                utilization: Some(80.0),
+                ..AlertRule::default()
+            },
+            "Session usage at 82%",
+        ),
+        (
+            AlertRule {
+                level: Some("warn".into()),
+                ..AlertRule::default()
+            },
+            "Session usage at 82% (danger)",
+        ),
+        (
+            AlertRule {
+                runs_out: Some(true),
+                ..AlertRule::default()
+            },
+            "Session usage at 82%, runs out in 1h 30m",
+        ),
+    ];
+    for (rule, message) in cases {
+        let alerts = Alerter::new(vec![desktop(rule)]).check(&data);
+        assert_eq!(alerts.len(), 1, "{message}");
+        assert_eq!(alerts[0].message, message);
+        assert_eq!(alerts[0].title(), "Claude Code usage");
+        assert_eq!(alerts[0].usage_level, "danger");
+    }
+}
+
+#[test]
+fn invalid_rules_are_rejected() {
+    let over_80 = AlertRule {
+        utilization: Some(80.0),
+        ..AlertRule::default()
+    };
+    let cases = [
+        (
+            desktop(AlertRule::default()),
+            "alert #1: set at least one of utilization",
+        ),
+        (over_80.clone(), "alert #1: set at least one of command"),
+        (
+            desktop(AlertRule {
+                window: Some("daily".into()),
+                ..over_80.clone()
+            }),
+            "alert #1: unknown window `daily`",
+        ),
+        (
+            desktop(AlertRule {
+                name: Some("Panic".into()),
+                level: Some("red".into()),
+                ..AlertRule::default()
+            }),
+            "alert Panic: unknown level `red`",
+        ),
+        (
+            desktop(AlertRule {
+                hysteresis: Some(-1.0),
+                ..over_80.clone()
+            }),
+            "alert #1: hysteresis must not be negative",
+        ),
+    ];
+    for (rule, prefix) in cases {
+        let err = alert::validate(&[rule]).unwrap_err().to_string();
+        assert!(err.starts_with(prefix), "{err}");
+    }
+    assert!(alert::validate(&[desktop(over_80)]).is_ok());
+}
+
+#[test]
+fn commands_get_the_alert_in_their_environment() {
+    let out = format!("{}/alert-env.txt", env!("CARGO_TARGET_TMPDIR"));
+    let rule = AlertRule {
+        utilization: Some(80.0),
+        command: Some(format!(
+            "printf '%s|%s|%s|%s' \"$ALERT_WINDOW\" \"$ALERT_UTILIZATION\" \"$ALERT_LEVEL\" \"$ALERT_MESSAGE\" > {out}"
+        )),
+        ..AlertRule::default()
+    };
+    let alerts = Alerter::new(vec![rule]).check(&session(85.0));
+    assert_eq!(alerts.len(), 1);
+    alert::dispatch(&alerts[0]).unwrap();
+    assert_eq!(
+        std::fs::read_to_string(&out).unwrap(),
+        "Session|85.0|normal|Session usage at 85%"
+    );
+
+    let failing = AlertRule {
+        utilization: Some(80.0),
+        command: Some("exit 3".into()),
+        ..AlertRule::default()
+    };
+    let alerts = Alerter::new(vec![failing]).check(&session(85.0));
+    let err = alert::dispatch(&alerts[0]).unwrap_err().to_string();
+    assert!(err.contains("`exit 3` exited with"), "{err}");
+}
@@ -0,0 +1,97 @@
+//! Tests for the per-hour and per-day allowances on the budget page.
+
+use chrono::{DateTime, Utc};
+use geekmagic_common::budget::{self, Allowance, Period};
+use geekmagic_common::schedule::WorkingHours;
+use geekmagic_common::stats::ActiveData;
+use serde_json::json;
+
+fn window(utilization: f64, resets_in: f64) -> serde_json::Value {
+    json!({
+        "utilization": utilization,
+        "resets_in_minutes": resets_in,
+        "usage_level": "normal",
+        "pace": null,
+    })
+}
+
+fn allowances(data: &ActiveData) -> Vec<(String, Allowance)> {
+    data.windows()
+        .into_iter()
+        .filter_map(|(label, w)| Some((label.to_string(), w.allowance.clone()?)))
+        .collect()
+}
+
+#[test]
+fn every_window_with_a_reset_gets_an_allowance() {
+    // Monday noon.
+    let now: DateTime<Utc> = "2026-01-05T12:00:00Z".parse().unwrap();
+    let mut data: ActiveData = serde_json::from_value(json!({
+        "five_hour": window(40.0, 150.0),
+        "seven_day": window(30.0, 3.5 * 1440.0),
+        "seven_day_opus": window(60.0, 1440.0),
+        "seven_day_sonnet": {
+            "utilization": 10.0,
+            "resets_in_minutes": null,
+            "usage_level": "normal",
+            "pace": null,
+        },
+    }))
+    .unwrap();
+    budget::apply(&mut data, None, now);
+    let expected = [
+        ("Session", 20.0, Period::Hour, 3),
+        ("Weekly", 17.5, Period::Day, 4),
+        ("Weekly Opus", 40.0, Period::Day, 1),
+    ];
+    let got = allowances(&data);
+    assert_eq!(got.len(), expected.len(), "{got:?}");
+    for ((label, allowance), (want_label, percent, period, periods)) in got.iter().zip(expected) {
+        assert_eq!(label, want_label);
+        assert_eq!(
+            *allowance,
+            Allowance {
+                percent,
+                period,
+                periods
+            },
+            "{label}"
+        );
+    }
+}
+
+#[test]
+fn working_days_spread_the_weekly_allowance() {
+    let office = WorkingHours::from_settings(
+        Some("09:00-17:00"),
+        Some(&["mon", "tue", "wed", "thu", "fri"].map(String::from)),
+        Some("UTC"),
+    )
+    .unwrap()
+    .unwrap();
+    // Thursday noon, resetting Monday noon: Thursday, Friday and Monday.
+    let now: DateTime<Utc> = "2026-01-08T12:00:00Z".parse().unwrap();
+    let mut data: ActiveData = serde_json::from_value(json!({
+        "five_hour": window(40.0, 150.0),
+        "seven_day": window(40.0, 4.0 * 1440.0),
+    }))
+    .unwrap();
+    budget::apply(&mut data, Some(&office), now);
+    let got = allowances(&data);
+    assert_eq!(got[0].1.period, Period::Hour);
+    assert_eq!(
+        got[1].1,
+        Allowance {
+            percent: 20.0,
+            period: Period::WorkingDay,
+            periods: 3
+        }
+    );
+
+    // Saturday noon, resetting Sunday: no working days left.
+    let now: DateTime<Utc> = "2026-01-10T12:00:00Z".parse().unwrap();
+    let mut data: ActiveData =
+        serde_json::from_value(json!({ "seven_day": window(40.0, 1440.0) })).unwrap();
+    budget::apply(&mut data, Some(&office), now);
+    assert_eq!(allowances(&data)[0].1.period, Period::Day);
+}
@@ -0,0 +1,464 @@
+//! Tests for finding and merging the config layers.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use geekmagic_common::config::{self, ConfigPaths, ConfigWatcher, Source};
+
+/// An empty directory under the target dir, named after the test.
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join(format!("config-{name}"));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn write(path: &Path, contents: &str) {
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, contents).unwrap();
+}
+
+/// A system directory and an XDG-style user directory, each with drop-ins.
+fn layered(name: &str) -> (PathBuf, ConfigPaths) {
+    let root = temp_dir(name);
+    let system = root.join("etc");
+    let user = root.join("home/geekmagic-stats/config.toml");
+    write(
+        &system.join("config.toml"),
+        "host = \"system\"\ndaemon = 60\nwith_disk = true\n",
+    );
+    write(&system.join("conf.d/20-b.toml"), "brightness = 20\n");
+    write(
+        &system.join("conf.d/10-a.toml"),
+        "host = \"system-drop-in\"\n",
+    );
+    write(&system.join("conf.d/notes.txt"), "not = \"read\"\n");
+    write(&user, "daemon = 120\nbrightness = 50\n");
+    write(
+        &user.parent().unwrap().join("conf.d/host.toml"),
+        "host = \"user-drop-in\"\n",
+    );
+    let paths = ConfigPaths {
+        system_dir: Some(system),
+        user_file: Some(user),
+        user_drop_ins: true,
+    };
+    (root, paths)
+}
+
+#[test]
+fn layers_in_merge_order() {
+    let (root, paths) = layered("order");
+    let layers: Vec<PathBuf> = paths
+        .layers()
+        .iter()
+        .map(|p| p.strip_prefix(&root).unwrap().to_path_buf())
+        .collect();
+    assert_eq!(
+        layers,
+        [
+            "etc/config.toml",
+            "etc/conf.d/10-a.toml",
+            "etc/conf.d/20-b.toml",
+            "home/geekmagic-stats/config.toml",
+            "home/geekmagic-stats/conf.d/host.toml",
+        ]
+        .map(PathBuf::from)
+    );
+}
+
+#[test]
+fn later_layers_win_key_by_key() {
+    let (root, paths) = layered("merge");
+    let loaded = config::load_from(&paths, None, &[]).unwrap();
+    assert_eq!(loaded.config.host.as_deref(), Some("user-drop-in"));
+    assert_eq!(loaded.config.daemon, Some(120));
+    assert_eq!(loaded.config.brightness, Some(50));
+    assert_eq!(loaded.config.with_disk, Some(true));
+    assert_eq!(loaded.source_of("host"), Source::File);
+    assert_eq!(
+        loaded.origin("host"),
+        Some(root.join("home/geekmagic-stats/conf.d/host.toml").as_path())
+    );
+    assert_eq!(
+        loaded.origin("with_disk"),
+        Some(root.join("etc/config.toml").as_path())
+    );
+    assert_eq!(loaded.files().len(), 5);
+}
+
+#[test]
+fn missing_files_are_listed_but_skipped() {
+    let root = temp_dir("missing");
+    let paths = ConfigPaths {
+        system_dir: Some(root.join("etc")),
+        user_file: Some(root.join("home/config.toml")),
+        user_drop_ins: true,
+    };
+    assert_eq!(
+        paths.layers(),
+        [root.join("etc/config.toml"), root.join("home/config.toml")]
+    );
+    let loaded = config::load_from(&paths, None, &[]).unwrap();
+    assert!(loaded.files().is_empty());
+    assert_eq!(loaded.config.host, None);
+}
+
+#[test]
+fn explicit_file_stands_alone() {
+    let root = temp_dir("explicit");
+    let file = root.join("config.toml");
+    write(&file, "host = \"explicit\"\n");
+    write(&root.join("conf.d/extra.toml"), "daemon = 10\n");
+
+    let paths = ConfigPaths::resolve(Some(file.to_str().unwrap()));
+    assert_eq!(
+        paths,
+        ConfigPaths {
+            system_dir: None,
+            user_file: Some(file.clone()),
+            user_drop_ins: false,
+        }
+    );
+    assert_eq!(paths.layers(), [file]);
+    let loaded = config::load_from(&paths, None, &[]).unwrap();
+    assert_eq!(loaded.config.host.as_deref(), Some("explicit"));
+    assert_eq!(loaded.config.daemon, None);
+}
+
+fn vars(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+    pairs
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect()
+}
+
+#[test]
+fn env_values_are_toml_literals_or_strings() {
+    let paths = ConfigPaths::default();
+    let env = vars(&[
+        ("GEEKMAGIC_DAEMON", "300"),
+        ("GEEKMAGIC_WITH_DISK", "true"),
+        ("GEEKMAGIC_WORKING_DAYS", r#"["mon", "tue"]"#),
+        // Not valid TOML, so taken as a string.
+        ("GEEKMAGIC_HOST", "10.0.1.102"),
+        ("GEEKMAGIC_SOURCE", r#""file:/tmp/usage.json""#),
+        ("HOME", "/ignored"),
+    ]);
+    let loaded = config::load_from(&paths, None, &env).unwrap();
+    let cfg = &loaded.config;
+    assert_eq!(cfg.daemon, Some(300));
+    assert_eq!(cfg.with_disk, Some(true));
+    assert_eq!(
+        cfg.working_days.as_deref(),
+        Some(["mon".to_string(), "tue".to_string()].as_slice())
+    );
+    assert_eq!(cfg.host.as_deref(), Some("10.0.1.102"));
+    assert_eq!(cfg.source.as_deref(), Some("file:/tmp/usage.json"));
+    assert_eq!(loaded.source_of("daemon"), Source::Env);
+    assert!(loaded.warnings().is_empty());
+}
+
+#[test]
+fn env_overrides_files_and_nests_with_double_underscores() {
+    let root = temp_dir("env-nesting");
+    let file = root.join("config.toml");
+    write(
+        &file,
+        "host = \"file\"\n[profile.office]\nhost = \"office-file\"\nbrightness = 40\n",
+    );
+    let paths = ConfigPaths::resolve(Some(file.to_str().unwrap()));
+
+    let env = vars(&[("GEEKMAGIC_HOST", "env")]);
+    let loaded = config::load_from(&paths, None, &env).unwrap();
+    assert_eq!(loaded.config.host.as_deref(), Some("env"));
+
+    let env = vars(&[("GEEKMAGIC_PROFILE__OFFICE__HOST", "office-env")]);
+    let loaded = config::load_from(&paths, Some("office"), &env).unwrap();
+    assert_eq!(loaded.config.host.as_deref(), Some("office-env"));
+    assert_eq!(loaded.config.brightness, Some(40));
+}
+
+#[test]
+fn unknown_env_variables_are_ignored_with_a_warning() {
+    let env = vars(&[
+        ("GEEKMAGIC_HSOT", "10.0.1.102"),
+        ("GEEKMAGIC_PROFILE__OFFICE__BRIGHTNES", "40"),
+        ("GEEKMAGIC_API_TOKEN", "secret"),
+    ]);
+    let loaded = config::load_from(&ConfigPaths::default(), None, &env).unwrap();
+    assert_eq!(loaded.config.host, None);
+    assert_eq!(
+        loaded.warnings(),
+        [
+            "ignoring GEEKMAGIC_HSOT: not a config key (did you mean `GEEKMAGIC_HOST`?)",
+            "ignoring GEEKMAGIC_PROFILE__OFFICE__BRIGHTNES: not a config key \
+             (did you mean `GEEKMAGIC_PROFILE__OFFICE__BRIGHTNESS`?)",
+            "ignoring GEEKMAGIC_API_TOKEN: not a config key",
+        ]
+    );
+}
+
+#[test]
+fn invalid_env_values_are_errors() {
+    let env = vars(&[("GEEKMAGIC_DAEMON", "soon")]);
+    let err = config::load_from(&ConfigPaths::default(), None, &env).unwrap_err();
+    assert!(
+        err.to_string().starts_with("invalid GEEKMAGIC_DAEMON:"),
+        "{err}"
+    );
+}
+
+/// Loads `contents` as the only config file and returns the error message.
+fn load_error(name: &str, contents: &str) -> String {
+    let file = temp_dir(name).join("config.toml");
+    write(&file, contents);
+    let paths = ConfigPaths::resolve(Some(file.to_str().unwrap()));
+    let err = config::load_from(&paths, None, &[]).unwrap_err();
+    format!("{err:#}")
+}
+
+#[test]
+fn unknown_keys_suggest_the_closest_one() {
+    let cases = [
+        ("hsot = \"x\"", Some("host")),
+        ("with_dsk = true", Some("with_disk")),
+        ("brightnes = 40", Some("brightness")),
+        ("stale_afer = 30", Some("stale_after")),
+        ("[profile.office]\nhots = \"x\"", Some("host")),
+        ("[[alert]]\nwindw = \"session\"", Some("window")),
+        ("colour_scheme = \"dark\"", None),
+        ("x = 1", None),
+    ];
+    for (i, (contents, hint)) in cases.into_iter().enumerate() {
+        let message = load_error(&format!("hint-{i}"), contents);
+        assert!(message.contains("unknown field"), "{contents}: {message}");
+        match hint {
+            Some(key) => assert!(
+                message.ends_with(&format!("help: did you mean `{key}`?")),
+                "{contents}: {message}"
+            ),
+            None => assert!(!message.contains("did you mean"), "{contents}: {message}"),
+        }
+    }
+}
+
+#[test]
+fn errors_point_at_the_file_and_line() {
+    let message = load_error("line", "host = \"x\"\n\nwith_dsk = true\n");
+    assert!(
+        message.starts_with("failed to parse config at "),
+        "{message}"
+    );
+    assert!(message.contains("line 3"), "{message}");
+}
+
+#[test]
+fn init_writes_the_starter_config_once() {
+    let path = temp_dir("init").join("nested/config.toml");
+    config::init(&path, false).unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), config::STARTER_CONFIG);
+
+    let err = config::init(&path, false).unwrap_err();
+    assert!(err.to_string().contains("--force"), "{err}");
+    fs::write(&path, "host = \"edited\"\n").unwrap();
+    config::init(&path, true).unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), config::STARTER_CONFIG);
+
+    // Everything is commented out, so it loads as the defaults.
+    let paths = ConfigPaths::resolve(Some(path.to_str().unwrap()));
+    let loaded = config::load_from(&paths, None, &[]).unwrap();
+    assert_eq!(loaded.config.host, None);
+}
+
+#[test]
+fn starter_config_examples_are_valid() {
+    // Uncomment every example setting and table header. Tables run to the
+    // next blank line, and top-level settings must come before them.
+    let (mut top, mut tables) = (String::new(), String::new());
+    let mut in_table = false;
+    for line in config::STARTER_CONFIG.lines() {
+        if line.is_empty() {
+            in_table = false;
+        }
+        let Some(line) = line.strip_prefix("# ") else {
+            continue;
+        };
+        let is_setting = line
+            .split_once(" = ")
+            .is_some_and(|(key, _)| key.chars().all(|c| c.is_ascii_lowercase() || c == '_'));
+        if line.starts_with('[') {
+            in_table = true;
+        } else if !is_setting {
+            continue;
+        }
+        let out = if in_table { &mut tables } else { &mut top };
+        out.push_str(line);
+        out.push('\n');
+    }
+    let uncommented = top + &tables;
+    let file = temp_dir("starter").join("config.toml");
+    write(&file, &uncommented);
+    let paths = ConfigPaths::resolve(Some(file.to_str().unwrap()));
+    let loaded = config::load_from(&paths, None, &[]).unwrap();
+    assert_eq!(loaded.config.alerts.len(), 2);
+    assert_eq!(loaded.profile(), Some("home"));
+    assert_eq!(loaded.config.host.as_deref(), Some("192.168.1.50"));
+}
+
+#[test]
+fn config_check_reports_settings_and_fails_on_typos() {
+    let dir = temp_dir("check");
+    let file = dir.join("config.toml");
+    write(&file, "host = \"10.0.1.102\"\nbrightness = 40\n");
+    let check = |file: &Path| {
+        std::process::Command::new(env!("CARGO_BIN_EXE_geekmagic-stats"))
+            .args(["--config", file.to_str().unwrap(), "config", "check"])
+            .env_remove("GEEKMAGIC_PROFILE")
+            .output()
+            .unwrap()
+    };
+
+    let output = check(&file);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "{stdout}");
+    assert!(
+        stdout.contains(&format!("{} (ok)", file.display())),
+        "{stdout}"
+    );
+    assert!(stdout.contains("brightness"), "{stdout}");
+    assert!(stdout.contains("40"), "{stdout}");
+
+    write(&file, "host = \"10.0.1.102\"\nbrightnes = 40\n");
+    let output = check(&file);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success());
+    assert!(
+        stderr.contains("help: did you mean `brightness`?"),
+        "{stderr}"
+    );
+}
+
+/// A file with two profiles, `home` being the default.
+fn profiles(name: &str) -> ConfigPaths {
+    let file = temp_dir(name).join("config.toml");
+    write(
+        &file,
+        "host = \"top\"\nbrightness = 80\ndaemon = 300\ndefault_profile = \"home\"\n\
+         [profile.home]\nhost = \"home\"\n\
+         [profile.office]\nhost = \"office\"\nbrightness = 40\n",
+    );
+    ConfigPaths::resolve(Some(file.to_str().unwrap()))
+}
+
+#[test]
+fn profile_selection_precedence() {
+    let paths = profiles("profile-precedence");
+    let env = vars(&[("GEEKMAGIC_PROFILE", "office")]);
+    let none = Vec::new();
+    // --profile beats GEEKMAGIC_PROFILE, which beats default_profile.
+    let cases = [
+        (Some("home"), &env, "home"),
+        (None, &env, "office"),
+        (None, &none, "home"),
+    ];
+    for (flag, env, expected) in cases {
+        let loaded = config::load_from(&paths, flag, env).unwrap();
+        assert_eq!(loaded.profile(), Some(expected), "{flag:?}");
+        assert_eq!(loaded.config.host.as_deref(), Some(expected), "{flag:?}");
+    }
+}
+
+#[test]
+fn profiles_inherit_and_sit_below_the_environment() {
+    let paths = profiles("profile-layers");
+    let loaded = config::load_from(&paths, Some("office"), &[]).unwrap();
+    assert_eq!(loaded.config.brightness, Some(40));
+    assert_eq!(loaded.source_of("brightness"), Source::Profile);
+    // Not set by the profile, so inherited from the top level.
+    assert_eq!(loaded.config.daemon, Some(300));
+    assert_eq!(loaded.source_of("daemon"), Source::File);
+
+    let env = vars(&[("GEEKMAGIC_BRIGHTNESS", "10")]);
+    let loaded = config::load_from(&paths, Some("office"), &env).unwrap();
+    assert_eq!(loaded.config.brightness, Some(10));
+    assert_eq!(loaded.source_of("brightness"), Source::Env);
+}
+
+#[test]
+fn unknown_profile_lists_the_defined_ones() {
+    let paths = profiles("profile-unknown");
+    let err = config::load_from(&paths, Some("ofice"), &[]).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "unknown profile `ofice` (did you mean `office`?); defined profiles: home, office"
+    );
+}
+
+#[test]
+fn brightness_is_range_checked_on_load() {
+    let message = load_error("brightness", "brightness = 150\n");
+    assert_eq!(message, "brightness must be between 0 and 100, got 150");
+    let env = vars(&[("GEEKMAGIC_BRIGHTNESS", "101")]);
+    assert!(config::load_from(&ConfigPaths::default(), None, &env).is_err());
+}
+
+/// Rewrites `path` and moves its mtime forward, so the change shows even on
+/// filesystems with coarse timestamps.
+fn edit(path: &Path, contents: &str, seconds_later: u64) {
+    write(path, contents);
+    let mtime = SystemTime::now() + Duration::from_secs(seconds_later);
+    fs::File::options()
+        .write(true)
+        .open(path)
+        .unwrap()
+        .set_modified(mtime)
+        .unwrap();
+}
+
+#[test]
+fn watcher_reloads_after_an_edit() {
+    let file = temp_dir("watch-edit").join("config.toml");
+    write(&file, "host = \"before\"\n");
+    let mut watcher =
+        ConfigWatcher::watch(ConfigPaths::resolve(Some(file.to_str().unwrap())), None);
+    assert!(watcher.poll().is_none());
+
+    edit(&file, "host = \"after\"\n", 10);
+    let cfg = watcher.poll().expect("edit noticed").unwrap();
+    assert_eq!(cfg.host.as_deref(), Some("after"));
+    // Reported once.
+    assert!(watcher.poll().is_none());
+
+    // A broken edit is reported as an error, then the fix as a new config.
+    edit(&file, "host = \n", 20);
+    assert!(watcher.poll().expect("edit noticed").is_err());
+    edit(&file, "host = \"fixed\"\n", 30);
+    let cfg = watcher.poll().expect("edit noticed").unwrap();
+    assert_eq!(cfg.host.as_deref(), Some("fixed"));
+}
+
+#[test]
+fn watcher_notices_new_and_removed_files() {
+    let (root, paths) = layered("watch-drop-ins");
+    let mut watcher = ConfigWatcher::watch(paths, None);
+    assert!(watcher.poll().is_none());
+
+    let drop_in = root.join("home/geekmagic-stats/conf.d/zz-daemon.toml");
+    write(&drop_in, "daemon = 45\n");
+    let cfg = watcher.poll().expect("new drop-in noticed").unwrap();
+    assert_eq!(cfg.daemon, Some(45));
+
+    fs::remove_file(&drop_in).unwrap();
+    let cfg = watcher.poll().expect("removal noticed").unwrap();
+    assert_eq!(cfg.daemon, Some(120));
+
+    // The user file appearing where there was none counts too.
+    let user = root.join("home/geekmagic-stats/config.toml");
+    fs::remove_file(&user).unwrap();
+    assert!(watcher.poll().is_some());
+    write(&user, "daemon = 90\n");
+    let cfg = watcher.poll().expect("new file noticed").unwrap();
+    assert_eq!(cfg.daemon, Some(90));
+}
@@ -0,0 +1,180 @@
+//! Tests for the Prometheus exposition of the daemon's telemetry.
+
+use chrono::Utc;
+use geekmagic_common::metrics;
+use geekmagic_common::network_render::{InterfaceInfo, NetworkInfo};
+use geekmagic_common::sensors_render::{Sensor, SensorInfo, SensorKind};
+use geekmagic_common::stats::ActiveData;
+use geekmagic_common::telemetry::{FetchRecord, Telemetry};
+use serde_json::json;
+
+fn usage() -> ActiveData {
+    serde_json::from_value(json!({
+        "five_hour": {
+            "utilization": 42.0,
+            "resets_in_minutes": 90.0,
+            "usage_level": "normal",
+            "pace": {
+                "expected_percent": 40.0,
+                "delta_percent": 2.0,
+                "will_last_to_reset": true,
+                "runs_out_in_minutes": null,
+            },
+        },
+        "seven_day": {
+            "utilization": 20.0,
+            "resets_in_minutes": null,
+            "usage_level": "normal",
+            "pace": null,
+        },
+        "seven_day_opus": {
+            "utilization": 75.5,
+            "resets_in_minutes": 1440.0,
+            "usage_level": "high",
+            "pace": null,
+        },
+    }))
+    .unwrap()
+}
+
+fn fetch(outcome: Result<&str, &str>) -> FetchRecord {
+    FetchRecord {
+        at: Utc::now(),
+        source: "claude-code-stats".to_string(),
+        outcome: outcome.map(str::to_string).map_err(str::to_string),
+        message: None,
+    }
+}
+
+fn sensor(id: &str, kind: SensorKind, value: f64) -> Sensor {
+    Sensor {
+        id: id.to_string(),
+        label: id.to_string(),
+        kind,
+        value,
+        high: None,
+        critical: None,
+    }
+}
+
+/// Sample lines (not `# HELP`/`# TYPE`) of `text`.
+fn samples(text: &str) -> Vec<&str> {
+    text.lines().filter(|l| !l.starts_with('#')).collect()
+}
+
+#[test]
+fn fresh_daemon_only_reports_counters() {
+    let text = metrics::encode(&Telemetry::default());
+    assert_eq!(
+        samples(&text),
+        ["geekmagic_updates_total 0", "geekmagic_errors_total 0"]
+    );
+    assert!(text.contains("# TYPE geekmagic_updates_total counter\n"));
+}
+
+#[test]
+fn every_usage_window_is_labelled() {
+    let telemetry = Telemetry {
+        last_fetch: Some(fetch(Ok("active"))),
+        usage: Some(usage()),
+        ..Telemetry::default()
+    };
+    let text = metrics::encode(&telemetry);
+    let lines = samples(&text);
+    for want in [
+        "geekmagic_usage_up 1",
+        "geekmagic_usage_utilization_percent{window=\"session\"} 42",
+        "geekmagic_usage_utilization_percent{window=\"weekly\"} 20",
+        "geekmagic_usage_utilization_percent{window=\"weekly_opus\"} 75.5",
+        "geekmagic_usage_expected_percent{window=\"session\"} 40",
+        "geekmagic_usage_pace_delta_percent{window=\"session\"} 2",
+        "geekmagic_usage_will_last_to_reset{window=\"session\"} 1",
+        "geekmagic_usage_resets_in_seconds{window=\"session\"} 5400",
+        "geekmagic_usage_resets_in_seconds{window=\"weekly_opus\"} 86400",
+    ] {
+        assert!(lines.contains(&want), "missing {want} in:\n{text}");
+    }
+    // Windows without a pace or reset are left out rather than zero.
+    assert!(!text.contains("geekmagic_usage_expected_percent{window=\"weekly\"}"));
+    assert!(!text.contains("geekmagic_usage_resets_in_seconds{window=\"weekly\"}"));
+    // One HELP/TYPE header per family.
+    assert_eq!(
+        text.matches("# TYPE geekmagic_usage_utilization_percent gauge")
+            .count(),
+        1
+    );
+}
+
+#[test]
+fn failed_fetch_drops_usage_and_reports_it_down() {
+    // Usage left over from before the failure must not be exported.
+    let telemetry = Telemetry {
+        last_fetch: Some(fetch(Err("401 Unauthorized"))),
+        usage: Some(usage()),
+        ..Telemetry::default()
+    };
+    let text = metrics::encode(&telemetry);
+    assert!(samples(&text).contains(&"geekmagic_usage_up 0"), "{text}");
+    assert!(
+        !text.contains("geekmagic_usage_utilization_percent"),
+        "{text}"
+    );
+
+    // A source reporting no data is down too.
+    let telemetry = Telemetry {
+        last_fetch: Some(fetch(Ok("logged out"))),
+        usage: None,
+        ..Telemetry::default()
+    };
+    let text = metrics::encode(&telemetry);
+    assert!(samples(&text).contains(&"geekmagic_usage_up 0"), "{text}");
+}
+
+#[test]
+fn readings_and_uploads_are_reported() {
+    let telemetry = Telemetry {
+        network: Some(NetworkInfo {
+            interfaces: vec![InterfaceInfo {
+                name: "eth0".to_string(),
+                rx_bytes: 1000,
+                tx_bytes: 2000,
+                rx_rate: 1250.5,
+                tx_rate: 0.0,
+            }],
+        }),
+        sensors: Some(SensorInfo {
+            sensors: vec![
+                sensor("coretemp/Package id 0", SensorKind::Temperature, 51.0),
+                sensor("it87/fan \"cpu\"", SensorKind::Fan, 1200.0),
+            ],
+            cpu_mhz: None,
+        }),
+        device_up: Some(false),
+        upload_seconds: Some(0.25),
+        upload_bytes: Some(20412),
+        last_success: Some(1_767_623_400),
+        updates_total: 7,
+        errors_total: 2,
+        ..Telemetry::default()
+    };
+    let text = metrics::encode(&telemetry);
+    let lines = samples(&text);
+    for want in [
+        "geekmagic_network_receive_bytes_per_second{interface=\"eth0\"} 1250.5",
+        "geekmagic_network_transmit_bytes_per_second{interface=\"eth0\"} 0",
+        "geekmagic_sensor_temperature_celsius{sensor=\"coretemp/Package id 0\"} 51",
+        "geekmagic_sensor_fan_rpm{sensor=\"it87/fan \\\"cpu\\\"\"} 1200",
+        "geekmagic_device_up 0",
+        "geekmagic_upload_duration_seconds 0.25",
+        "geekmagic_upload_bytes 20412",
+        "geekmagic_last_success_timestamp_seconds 1767623400",
+        "geekmagic_updates_total 7",
+        "geekmagic_errors_total 2",
+    ] {
+        assert!(lines.contains(&want), "missing {want} in:\n{text}");
+    }
+    // Nothing about pages that weren't sampled.
+    assert!(!text.contains("geekmagic_disk_"));
+    assert!(!text.contains("geekmagic_system_"));
+    assert!(!text.contains("geekmagic_usage_"));
+}
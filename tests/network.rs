@@ -0,0 +1,64 @@
+//! Tests for the network page's counter parsers, on a captured
+//! `/proc/net/dev` (`tests/fixtures/proc/net-dev`) and macOS `netstat -ib`
+//! output (`tests/fixtures/netstat-ib.txt`).
+
+use std::fs;
+use std::path::PathBuf;
+
+use geekmagic_common::network_render::{parse_netstat, parse_proc_net_dev, Counters};
+
+fn fixture(name: &str) -> String {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(name);
+    fs::read_to_string(path).unwrap()
+}
+
+fn counters(entries: &[(&str, u64, u64)]) -> Counters {
+    entries
+        .iter()
+        .map(|&(name, rx, tx)| (name.to_string(), (rx, tx)))
+        .collect()
+}
+
+#[test]
+fn proc_net_dev_reads_receive_and_transmit_bytes() {
+    assert_eq!(
+        parse_proc_net_dev(&fixture("proc/net-dev")).unwrap(),
+        counters(&[
+            ("docker0", 8_133_402, 212_334_918),
+            ("enp5s0", 61_826_312_144, 4_823_449_122),
+            ("lo", 185_488_300, 185_488_300),
+            ("wlp4s0", 0, 0),
+        ])
+    );
+}
+
+#[test]
+fn malformed_proc_net_dev_names_the_interface() {
+    let text = "Inter-|\n face |\n  eth0: 12 34\n";
+    let err = parse_proc_net_dev(text).unwrap_err();
+    assert_eq!(err.to_string(), "malformed /proc/net/dev line for eth0");
+    assert!(parse_proc_net_dev("Inter-|\n face |\n").unwrap().is_empty());
+}
+
+#[test]
+fn netstat_reads_the_link_rows() {
+    assert_eq!(
+        parse_netstat(&fixture("netstat-ib.txt")).unwrap(),
+        counters(&[
+            ("en0", 61_826_312_144, 4_823_449_122),
+            ("gif0", 0, 0),
+            ("lo0", 318_276_935, 318_276_935),
+            ("utun0", 816, 6_504),
+        ])
+    );
+}
+
+#[test]
+fn malformed_netstat_names_the_interface() {
+    let text = "Name Mtu Network Address Ipkts Ierrs Ibytes Opkts Oerrs Obytes Coll\n\
+                en0 1500 <Link#6> a4:83:e7:1c:22:9f 1 0 lots 1 0 2 0\n";
+    let err = parse_netstat(text).unwrap_err();
+    assert_eq!(err.to_string(), "malformed netstat line for en0");
+}
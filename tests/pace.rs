@@ -0,0 +1,619 @@
+//! Tests for the local pace computation.
+//!
+//! `compute_pace` fills in pace for sources that leave it out and is meant to
+//! match claude-code-stats' own, so besides the table of branches and the
+//! properties below, it is checked against the upstream crate directly. The
+//! fixtures in `tests/fixtures/` are payloads in the shape upstream emits.
+//! The pace models that refine the run-out estimate from history come last.
+
+use std::fs;
+use std::path::PathBuf;
+
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use claude_code_stats::types::{to_usage_window, ApiWindow};
+use geekmagic_common::pace::{self, estimator, project, History, PaceModel, Sample};
+use geekmagic_common::source;
+use geekmagic_common::stats::{
+    compute_pace, fetch_stats, ActiveData, PaceInfo, Stats, UsageWindow, FIVE_HOUR_MINUTES,
+    SEVEN_DAY_MINUTES,
+};
+use proptest::prelude::*;
+
+const EPSILON: f64 = 1e-9;
+
+enum Expect {
+    NoPace,
+    Lasts { expected: f64 },
+    RunsOut { expected: f64, eta: f64 },
+}
+
+struct Case {
+    name: &'static str,
+    utilization: f64,
+    resets_in: f64,
+    window: f64,
+    expect: Expect,
+}
+
+const fn case(
+    name: &'static str,
+    utilization: f64,
+    resets_in: f64,
+    window: f64,
+    expect: Expect,
+) -> Case {
+    Case {
+        name,
+        utilization,
+        resets_in,
+        window,
+        expect,
+    }
+}
+
+const CASES: &[Case] = &[
+    case("zero-length window", 10.0, 10.0, 0.0, Expect::NoPace),
+    case("reset time reached", 50.0, 0.0, 300.0, Expect::NoPace),
+    case("reset time in the past", 50.0, -5.0, 300.0, Expect::NoPace),
+    case(
+        "reset beyond the window",
+        50.0,
+        301.0,
+        300.0,
+        Expect::NoPace,
+    ),
+    case("just opened with usage", 1.0, 300.0, 300.0, Expect::NoPace),
+    case(
+        "just opened without usage",
+        0.0,
+        300.0,
+        300.0,
+        Expect::NoPace,
+    ),
+    case("expected under 3%", 5.0, 291.3, 300.0, Expect::NoPace),
+    case(
+        "expected at 3%",
+        0.0,
+        291.0,
+        300.0,
+        Expect::Lasts { expected: 3.0 },
+    ),
+    case(
+        "nothing used",
+        0.0,
+        150.0,
+        300.0,
+        Expect::Lasts { expected: 50.0 },
+    ),
+    case(
+        "behind pace",
+        20.0,
+        150.0,
+        300.0,
+        Expect::Lasts { expected: 50.0 },
+    ),
+    // Runs out exactly at the reset, which counts as lasting.
+    case(
+        "exactly on pace",
+        50.0,
+        150.0,
+        300.0,
+        Expect::Lasts { expected: 50.0 },
+    ),
+    case(
+        "ahead of pace",
+        60.0,
+        150.0,
+        300.0,
+        Expect::RunsOut {
+            expected: 50.0,
+            eta: 100.0,
+        },
+    ),
+    case(
+        "at the limit",
+        100.0,
+        150.0,
+        300.0,
+        Expect::RunsOut {
+            expected: 50.0,
+            eta: 0.0,
+        },
+    ),
+    case(
+        "over the limit",
+        120.0,
+        150.0,
+        300.0,
+        Expect::RunsOut {
+            expected: 50.0,
+            eta: 0.0,
+        },
+    ),
+    case(
+        "negative utilization",
+        -5.0,
+        150.0,
+        300.0,
+        Expect::Lasts { expected: 50.0 },
+    ),
+    case(
+        "weekly window late",
+        91.0,
+        1008.0,
+        10080.0,
+        Expect::RunsOut {
+            expected: 90.0,
+            // 9 points left at 91 points per 9072 minutes.
+            eta: 9.0 * 9072.0 / 91.0,
+        },
+    ),
+];
+
+fn close(a: f64, b: f64) -> bool {
+    (a - b).abs() < EPSILON
+}
+
+#[test]
+fn compute_pace_table() {
+    for c in CASES {
+        let pace = compute_pace(c.utilization, c.resets_in, c.window);
+        let actual = c.utilization.clamp(0.0, 100.0);
+        match (&c.expect, &pace) {
+            (Expect::NoPace, None) => {}
+            (Expect::Lasts { expected }, Some(p)) => {
+                assert!(close(p.expected_percent, *expected), "{}: {p:?}", c.name);
+                assert!(
+                    close(p.delta_percent, actual - expected),
+                    "{}: {p:?}",
+                    c.name
+                );
+                assert!(p.will_last_to_reset, "{}: {p:?}", c.name);
+                assert_eq!(p.eta_minutes, None, "{}", c.name);
+            }
+            (Expect::RunsOut { expected, eta }, Some(p)) => {
+                assert!(close(p.expected_percent, *expected), "{}: {p:?}", c.name);
+                assert!(
+                    close(p.delta_percent, actual - expected),
+                    "{}: {p:?}",
+                    c.name
+                );
+                assert!(!p.will_last_to_reset, "{}: {p:?}", c.name);
+                let got = p.eta_minutes.unwrap_or(f64::NAN);
+                assert!(close(got, *eta), "{}: eta {got}, want {eta}", c.name);
+            }
+            _ => panic!("{}: unexpected pace {pace:?}", c.name),
+        }
+    }
+}
+
+/// Pace as claude-code-stats computes it, read back through our types.
+fn upstream_pace(utilization: f64, resets_in_seconds: i64, window: f64) -> Option<PaceInfo> {
+    let now = Utc.timestamp_opt(1_800_000_000, 0).unwrap();
+    let api = ApiWindow {
+        utilization: Some(utilization),
+        resets_at: Some((now + Duration::seconds(resets_in_seconds)).to_rfc3339()),
+    };
+    let upstream = to_usage_window(&api, now, Some(window));
+    let ours: UsageWindow =
+        serde_json::from_value(serde_json::to_value(upstream).unwrap()).unwrap();
+    ours.pace
+}
+
+fn as_json(pace: &Option<PaceInfo>) -> serde_json::Value {
+    serde_json::to_value(pace).unwrap()
+}
+
+fn window_minutes() -> impl Strategy<Value = f64> {
+    prop_oneof![Just(FIVE_HOUR_MINUTES), Just(SEVEN_DAY_MINUTES)]
+}
+
+proptest! {
+    #[test]
+    fn delta_is_actual_minus_expected(
+        utilization in -20.0..150.0f64,
+        resets_in in 0.0..11_000.0f64,
+        window in window_minutes(),
+    ) {
+        if let Some(p) = compute_pace(utilization, resets_in, window) {
+            let actual = utilization.clamp(0.0, 100.0);
+            prop_assert!(close(p.delta_percent, actual - p.expected_percent));
+            prop_assert!((3.0..=100.0).contains(&p.expected_percent));
+        }
+    }
+
+    #[test]
+    fn eta_only_when_not_lasting(
+        utilization in -20.0..150.0f64,
+        resets_in in 0.0..11_000.0f64,
+        window in window_minutes(),
+    ) {
+        if let Some(p) = compute_pace(utilization, resets_in, window) {
+            prop_assert_eq!(p.eta_minutes.is_some(), !p.will_last_to_reset);
+            if let Some(eta) = p.eta_minutes {
+                prop_assert!(eta >= 0.0 && eta < resets_in, "eta {} resets in {}", eta, resets_in);
+            }
+        }
+    }
+
+    #[test]
+    fn pace_exists_exactly_for_valid_windows(
+        utilization in 0.0..100.0f64,
+        resets_in in -10.0..11_000.0f64,
+        window in window_minutes(),
+    ) {
+        let expected = (window - resets_in) / window * 100.0;
+        // Float rounding decides the exact 3% boundary; stay clear of it.
+        prop_assume!((expected - 3.0).abs() > 1e-6);
+        let valid = resets_in > 0.0 && resets_in <= window && expected >= 3.0;
+        prop_assert_eq!(compute_pace(utilization, resets_in, window).is_some(), valid);
+    }
+
+    #[test]
+    fn more_usage_runs_out_sooner(
+        low in 0.0..100.0f64,
+        extra in 0.0..50.0f64,
+        resets_in in 1.0..300.0f64,
+        window in window_minutes(),
+    ) {
+        let high = low + extra;
+        let (Some(a), Some(b)) = (
+            compute_pace(low, resets_in, window),
+            compute_pace(high, resets_in, window),
+        ) else {
+            return Ok(());
+        };
+        prop_assert!(b.delta_percent >= a.delta_percent);
+        if !a.will_last_to_reset {
+            prop_assert!(!b.will_last_to_reset);
+        }
+        if let (Some(eta_low), Some(eta_high)) = (a.eta_minutes, b.eta_minutes) {
+            prop_assert!(eta_high <= eta_low + EPSILON);
+        }
+    }
+
+    #[test]
+    fn same_usage_later_is_safer(
+        utilization in 0.0..100.0f64,
+        early in 0.0..0.97f64,
+        later in 0.0..1.0f64,
+        window in window_minutes(),
+    ) {
+        // Fractions of the window still to go, `late` closer to the reset.
+        let early_resets_in = window * early;
+        let late_resets_in = early_resets_in * later;
+        let (Some(a), Some(b)) = (
+            compute_pace(utilization, early_resets_in, window),
+            compute_pace(utilization, late_resets_in, window),
+        ) else {
+            return Ok(());
+        };
+        prop_assert!(b.expected_percent >= a.expected_percent);
+        if !b.will_last_to_reset {
+            prop_assert!(!a.will_last_to_reset);
+        }
+    }
+
+    #[test]
+    fn matches_upstream(
+        utilization in -20.0..150.0f64,
+        resets_in_seconds in -600i64..700_000,
+        window in window_minutes(),
+    ) {
+        // Upstream floors a reset in the past to zero minutes.
+        let resets_in = (resets_in_seconds as f64 / 60.0).max(0.0);
+        prop_assert_eq!(
+            as_json(&compute_pace(utilization, resets_in, window)),
+            as_json(&upstream_pace(utilization, resets_in_seconds, window))
+        );
+    }
+}
+
+fn fixture(name: &str) -> ActiveData {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(name);
+    let source = source::parse(&format!("file:{}", path.display())).unwrap();
+    match fetch_stats(source.as_ref()).unwrap() {
+        Stats::Active(data) => *data,
+        other => panic!("{name}: expected active data, got {other:?}"),
+    }
+}
+
+fn windows(data: &ActiveData) -> [(&'static str, Option<&UsageWindow>, f64); 2] {
+    [
+        ("five_hour", data.five_hour.as_ref(), FIVE_HOUR_MINUTES),
+        ("seven_day", data.seven_day.as_ref(), SEVEN_DAY_MINUTES),
+    ]
+}
+
+#[test]
+fn upstream_fixtures_agree_with_compute_pace() {
+    for name in ["on-pace.json", "high-usage.json", "over-limit.json"] {
+        let data = fixture(name);
+        for (key, window, minutes) in windows(&data) {
+            let w = window.unwrap_or_else(|| panic!("{name}: no {key}"));
+            assert!(w.pace.is_some(), "{name}: {key} has no pace");
+            let local = compute_pace(w.utilization, w.resets_in_minutes.unwrap(), minutes);
+            assert_eq!(as_json(&w.pace), as_json(&local), "{name}: {key}");
+        }
+    }
+}
+
+#[test]
+fn missing_pace_is_filled_in_like_upstream() {
+    let upstream = fixture("high-usage.json");
+    let local = fixture("without-pace.json");
+    for ((key, a, _), (_, b, _)) in windows(&upstream).into_iter().zip(windows(&local)) {
+        assert_eq!(
+            as_json(&a.unwrap().pace),
+            as_json(&b.unwrap().pace),
+            "{key}"
+        );
+    }
+}
+
+#[test]
+fn no_pace_right_after_a_reset() {
+    let data = fixture("window-just-started.json");
+    for (key, window, _) in windows(&data) {
+        assert!(window.unwrap().pace.is_none(), "{key}");
+    }
+}
+
+#[test]
+fn per_model_windows_get_labels_and_pace() {
+    let data = fixture("per-model-windows.json");
+    let keys: Vec<&str> = data.extra.keys().map(String::as_str).collect();
+    assert_eq!(keys, ["seven_day_opus", "seven_day_sonnet"]);
+    for (key, label) in [
+        ("seven_day_opus", "Weekly Opus"),
+        ("seven_day_sonnet", "Weekly Sonnet"),
+    ] {
+        let extra = &data.extra[key];
+        assert_eq!(extra.label, label);
+        assert_eq!(extra.window_minutes, Some(SEVEN_DAY_MINUTES));
+        let w = &extra.window;
+        let local = compute_pace(
+            w.utilization,
+            w.resets_in_minutes.unwrap(),
+            SEVEN_DAY_MINUTES,
+        );
+        assert!(local.is_some(), "{key}");
+        assert_eq!(as_json(&w.pace), as_json(&local), "{key}");
+    }
+}
+
+// Pace models: the estimators built from recorded history, and projecting
+// with them. Weekday rates follow local time, so those tests pin `TZ`.
+
+fn utc(text: &str) -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339(text).unwrap().into()
+}
+
+/// Samples `(minutes before now, utilization)`.
+fn samples_before(now: DateTime<Utc>, points: &[(i64, f64)]) -> Vec<Sample> {
+    points
+        .iter()
+        .map(|&(minutes, utilization)| Sample {
+            at: (now - Duration::minutes(minutes)).timestamp(),
+            utilization,
+        })
+        .collect()
+}
+
+fn rate(model: PaceModel, window: &str, samples: &[Sample], now: DateTime<Utc>) -> Option<f64> {
+    estimator(model, window, samples, now).map(|e| e.rate_at(now))
+}
+
+#[test]
+fn thin_history_keeps_the_linear_pace() {
+    let now = utc("2026-03-04T12:00:00Z");
+    let ten_minutes = samples_before(now, &[(10, 20.0), (0, 25.0)]);
+    let single = samples_before(now, &[(0, 25.0)]);
+    for model in [PaceModel::Linear, PaceModel::Recent, PaceModel::Smoothed] {
+        assert!(rate(model, "five_hour", &[], now).is_none(), "{model}");
+        assert!(rate(model, "five_hour", &single, now).is_none(), "{model}");
+    }
+    // Two samples minutes apart say little about the rate.
+    assert!(rate(PaceModel::Recent, "five_hour", &ten_minutes, now).is_none());
+    assert!(rate(PaceModel::Smoothed, "five_hour", &ten_minutes, now).is_some());
+    // Six days aren't enough for per-weekday averages.
+    let six_days = samples_before(now, &[(6 * 1440, 10.0), (0, 40.0)]);
+    assert!(rate(PaceModel::Weekday, "seven_day", &six_days, now).is_none());
+    assert!(rate(PaceModel::Linear, "seven_day", &six_days, now).is_none());
+}
+
+#[test]
+fn recent_rate_starts_at_the_last_reset() {
+    let now = utc("2026-03-04T12:00:00Z");
+    let samples = samples_before(now, &[(110, 90.0), (60, 5.0), (30, 10.0), (0, 15.0)]);
+    let got = rate(PaceModel::Recent, "five_hour", &samples, now).unwrap();
+    assert!(close(got, 10.0 / 60.0), "{got}");
+    // Only the last two hours count.
+    let samples = samples_before(now, &[(300, 0.0), (120, 60.0), (0, 72.0)]);
+    let got = rate(PaceModel::Recent, "five_hour", &samples, now).unwrap();
+    assert!(close(got, 0.1), "{got}");
+    // The session window never uses weekday averages.
+    let weekday = rate(PaceModel::Weekday, "five_hour", &samples, now).unwrap();
+    assert!(close(weekday, 0.1), "{weekday}");
+}
+
+#[test]
+fn smoothed_rate_follows_a_steady_burn() {
+    let now = utc("2026-03-04T12:00:00Z");
+    // A steep climb, a reset, then one point every ten minutes.
+    let samples = samples_before(
+        now,
+        &[
+            (100, 0.0),
+            (90, 40.0),
+            (80, 80.0),
+            (60, 0.0),
+            (50, 1.0),
+            (40, 2.0),
+            (30, 3.0),
+            (20, 4.0),
+            (10, 5.0),
+            (0, 6.0),
+        ],
+    );
+    let got = rate(PaceModel::Smoothed, "five_hour", &samples, now).unwrap();
+    assert!(close(got, 0.1), "{got}");
+    // A burst pulls the average up without replacing it.
+    let mut bursty = samples.clone();
+    bursty.push(Sample {
+        at: (now + Duration::minutes(10)).timestamp(),
+        utilization: 16.0,
+    });
+    let got = rate(PaceModel::Smoothed, "five_hour", &bursty, now).unwrap();
+    assert!(got > 0.1 && got < 1.0, "{got}");
+}
+
+/// Two weeks of weekly-window samples from Monday 2026-01-05: ten points per
+/// weekday (five by 13:00, ten by 18:00), none at weekends, and a reset on
+/// Monday 2026-01-12 at 10:00.
+fn two_working_weeks() -> (Vec<Sample>, DateTime<Utc>) {
+    let start = utc("2026-01-05T00:00:00Z");
+    let mut samples = Vec::new();
+    let mut utilization = 0.0;
+    for day in 0..14 {
+        let midnight = start + Duration::days(day);
+        let weekday = day % 7 < 5;
+        for (hour, gained) in [(8, 0.0), (13, 5.0), (18, 5.0)] {
+            if day == 7 && hour == 13 {
+                utilization = 0.0;
+            }
+            if weekday {
+                utilization += gained;
+            }
+            samples.push(Sample {
+                at: (midnight + Duration::hours(hour)).timestamp(),
+                utilization,
+            });
+        }
+    }
+    (samples, utc("2026-01-18T20:00:00Z"))
+}
+
+#[test]
+fn weekday_rates_average_each_day_including_reset_days() {
+    std::env::set_var("TZ", "UTC");
+    let (samples, now) = two_working_weeks();
+    let estimator = estimator(PaceModel::Weekday, "seven_day", &samples, now).unwrap();
+    // Monday (the reset day) and Friday used ten points each week.
+    for day in ["2026-01-19T12:00:00Z", "2026-01-23T12:00:00Z"] {
+        let got = estimator.rate_at(utc(day));
+        assert!(close(got, 10.0 / 1440.0), "{day}: {got}");
+    }
+    for day in ["2026-01-24T12:00:00Z", "2026-01-25T12:00:00Z"] {
+        assert_eq!(estimator.rate_at(utc(day)), 0.0, "{day}");
+    }
+}
+
+#[test]
+fn projections_cross_day_boundaries() {
+    std::env::set_var("TZ", "UTC");
+    let (samples, now) = two_working_weeks();
+    let estimator = estimator(PaceModel::Weekday, "seven_day", &samples, now).unwrap();
+    // Sunday 20:00, resetting Monday 10:00: nothing burns until midnight,
+    // then ten points a day.
+    let resets_in = 14.0 * 60.0;
+    let eta = project(estimator.as_ref(), 96.0, resets_in, now).unwrap();
+    assert!(close(eta, 240.0 + 4.0 * 144.0), "{eta}");
+    assert_eq!(project(estimator.as_ref(), 95.0, resets_in, now), None);
+    assert_eq!(
+        project(estimator.as_ref(), 100.0, resets_in, now),
+        Some(0.0)
+    );
+    // Over a weekend nothing runs out.
+    let saturday = utc("2026-01-24T00:00:00Z");
+    assert_eq!(
+        project(estimator.as_ref(), 99.0, 2.0 * 1440.0, saturday),
+        None
+    );
+}
+
+fn weekly_window(utilization: f64, resets_in: f64) -> ActiveData {
+    let pace = compute_pace(utilization, resets_in, SEVEN_DAY_MINUTES);
+    serde_json::from_value(serde_json::json!({
+        "seven_day": {
+            "utilization": utilization,
+            "resets_in_minutes": resets_in,
+            "usage_level": "high",
+            "pace": pace,
+        }
+    }))
+    .unwrap()
+}
+
+#[test]
+fn apply_replaces_the_linear_estimate_when_history_allows() {
+    std::env::set_var("TZ", "UTC");
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("pace-apply");
+    fs::create_dir_all(&dir).unwrap();
+    let (samples, now) = two_working_weeks();
+    let path = dir.join("history.json");
+    fs::write(
+        &path,
+        serde_json::json!({ "seven_day": samples }).to_string(),
+    )
+    .unwrap();
+    let history = History::load_from(path).unwrap();
+
+    let linear_eta = |data: &ActiveData| data.seven_day.as_ref().unwrap().pace.clone().unwrap();
+    let mut data = weekly_window(96.0, 840.0);
+    let linear = linear_eta(&data);
+    assert!(!linear.will_last_to_reset);
+
+    pace::apply(PaceModel::Linear, &history, &mut data, now);
+    assert_eq!(
+        as_json(&Some(linear_eta(&data))),
+        as_json(&Some(linear.clone()))
+    );
+
+    pace::apply(PaceModel::Weekday, &history, &mut data, now);
+    let weekday = linear_eta(&data);
+    assert!(close(weekday.eta_minutes.unwrap(), 816.0), "{weekday:?}");
+    assert_eq!(weekday.expected_percent, linear.expected_percent);
+
+    // Without history the linear estimate stands.
+    let mut data = weekly_window(96.0, 840.0);
+    pace::apply(PaceModel::Weekday, &History::default(), &mut data, now);
+    assert_eq!(as_json(&Some(linear_eta(&data))), as_json(&Some(linear)));
+}
+
+#[test]
+fn history_is_spaced_pruned_and_saved() {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("pace-history");
+    let _ = fs::remove_dir_all(&dir);
+    let path = dir.join("state/history.json");
+    let mut history = History::load_from(path.clone()).unwrap();
+    assert!(history.samples("seven_day").is_empty());
+
+    let start = utc("2026-03-04T12:00:00Z");
+    history.record(&weekly_window(10.0, 5000.0), start);
+    // Closer than five minutes to the last sample: skipped.
+    history.record(&weekly_window(11.0, 4999.0), start + Duration::minutes(1));
+    history.record(&weekly_window(12.0, 4990.0), start + Duration::minutes(10));
+    let utilizations = |h: &History| -> Vec<f64> {
+        h.samples("seven_day")
+            .iter()
+            .map(|s| s.utilization)
+            .collect()
+    };
+    assert_eq!(utilizations(&history), [10.0, 12.0]);
+    assert!(history.samples("five_hour").is_empty());
+
+    history.save().unwrap();
+    let loaded = History::load_from(path.clone()).unwrap();
+    assert_eq!(utilizations(&loaded), [10.0, 12.0]);
+
+    // Four weeks on, the old samples are dropped.
+    history.record(&weekly_window(1.0, 9000.0), start + Duration::days(29));
+    assert_eq!(utilizations(&history), [1.0]);
+
+    fs::write(&path, "not json").unwrap();
+    let err = History::load_from(path).unwrap_err().to_string();
+    assert!(err.starts_with("failed to parse"), "{err}");
+}
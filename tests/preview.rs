@@ -0,0 +1,189 @@
+//! Tests for the terminal preview encoders, on small images written into a
+//! buffer.
+
+use std::env;
+
+use base64::Engine;
+use geekmagic_common::preview::{self, PreviewMode};
+use image::{Rgba, RgbaImage};
+
+const BLACK: Rgba<u8> = Rgba([0, 0, 0, 255]);
+const WHITE: Rgba<u8> = Rgba([255, 255, 255, 255]);
+const RED: Rgba<u8> = Rgba([255, 0, 0, 255]);
+
+/// An image whose PNG doesn't compress, so kitty needs several chunks.
+fn noise(w: u32, h: u32) -> RgbaImage {
+    let mut state = 0x2545_f491_u32;
+    RgbaImage::from_fn(w, h, |_, _| {
+        state ^= state << 13;
+        state ^= state >> 17;
+        state ^= state << 5;
+        let [r, g, b, _] = state.to_le_bytes();
+        Rgba([r, g, b, 255])
+    })
+}
+
+/// Splits kitty output into (control data, payload) per escape sequence.
+fn kitty_chunks(out: &str) -> Vec<(&str, &str)> {
+    out.strip_suffix("\x1b\\")
+        .unwrap()
+        .split("\x1b\\")
+        .map(|seq| {
+            let seq = seq.strip_prefix("\x1b_G").unwrap();
+            seq.split_once(';').unwrap()
+        })
+        .collect()
+}
+
+fn decode_png(base64: &str) -> RgbaImage {
+    let png = base64::engine::general_purpose::STANDARD
+        .decode(base64)
+        .unwrap();
+    image::load_from_memory(&png).unwrap().to_rgba8()
+}
+
+#[test]
+fn kitty_sends_a_small_image_in_one_chunk() {
+    let img = RgbaImage::from_pixel(4, 4, RED);
+    let mut out = Vec::new();
+    preview::write_kitty(&mut out, &img).unwrap();
+    let out = String::from_utf8(out).unwrap();
+    let chunks = kitty_chunks(&out);
+    assert_eq!(chunks.len(), 1);
+    assert_eq!(chunks[0].0, "f=100,a=T,m=0");
+    assert_eq!(decode_png(chunks[0].1), img);
+}
+
+#[test]
+fn kitty_splits_large_payloads_into_4096_byte_chunks() {
+    let img = noise(64, 64);
+    let mut out = Vec::new();
+    preview::write_kitty(&mut out, &img).unwrap();
+    let out = String::from_utf8(out).unwrap();
+    let chunks = kitty_chunks(&out);
+    assert!(chunks.len() > 2, "{} chunks", chunks.len());
+
+    let (last, rest) = chunks.split_last().unwrap();
+    assert_eq!(rest[0].0, "f=100,a=T,m=1");
+    for (control, _) in &rest[1..] {
+        assert_eq!(*control, "m=1");
+    }
+    assert_eq!(last.0, "m=0");
+    for (_, payload) in rest {
+        assert_eq!(payload.len(), 4096);
+    }
+    assert!(!last.1.is_empty() && last.1.len() <= 4096);
+
+    let payload: String = chunks.iter().map(|(_, p)| *p).collect();
+    assert_eq!(decode_png(&payload), img);
+}
+
+#[test]
+fn sixel_writes_each_band_per_color_with_runs() {
+    // 5x7: two bands, the second one row high.
+    let mut img = RgbaImage::from_pixel(5, 7, BLACK);
+    for x in 0..5 {
+        img.put_pixel(x, 0, RED);
+    }
+    img.put_pixel(0, 6, WHITE);
+    img.put_pixel(4, 6, WHITE);
+
+    let mut out = Vec::new();
+    preview::write_sixel(&mut out, &img).unwrap();
+    let out = String::from_utf8(out).unwrap();
+
+    let body = out.strip_prefix("\x1bPq\"1;1;5;7").unwrap();
+    // The whole 6x6x6 cube is defined up front, in percent.
+    let palette_end = body.find("#215;2;100;100;100").unwrap() + "#215;2;100;100;100".len();
+    let palette = &body[..palette_end];
+    assert!(palette.starts_with("#0;2;0;0;0#1;2;0;0;20#"));
+    assert!(palette.contains("#180;2;100;0;0#"));
+    assert_eq!(palette.matches('#').count(), 216);
+
+    // Band 1: black in rows 1-5 ('?' + 0b111110), red in row 0 ('?' + 1),
+    // five columns each so both are runs. Band 2: only row 0 is set, and
+    // short runs are written out.
+    assert_eq!(
+        &body[palette_end..],
+        "#0!5}$#180!5@$-#0?@@@?$#215@???@$-\x1b\\"
+    );
+}
+
+#[test]
+fn blocks_fit_the_columns_two_pixels_per_cell() {
+    let img = RgbaImage::from_pixel(40, 40, RED);
+    let cell = "\x1b[38;2;255;0;0m\x1b[48;2;255;0;0m\u{2580}";
+
+    let mut out = Vec::new();
+    preview::write_blocks(&mut out, &img, 80).unwrap();
+    let out = String::from_utf8(out).unwrap();
+    let lines: Vec<&str> = out.lines().collect();
+    // Never wider than the image: 40 columns, 40 pixel rows in 20 lines.
+    assert_eq!(lines.len(), 20);
+    assert_eq!(lines[0], format!("{}\x1b[0m", cell.repeat(40)));
+
+    // Narrow terminals still get 20 columns.
+    let mut out = Vec::new();
+    preview::write_blocks(&mut out, &img, 8).unwrap();
+    let out = String::from_utf8(out).unwrap();
+    let lines: Vec<&str> = out.lines().collect();
+    assert_eq!(lines.len(), 10);
+    assert!(lines.iter().all(|l| l.matches('\u{2580}').count() == 20));
+}
+
+#[test]
+fn blocks_repeat_the_last_row_of_an_odd_height() {
+    let mut img = RgbaImage::from_pixel(20, 3, BLACK);
+    for x in 0..20 {
+        img.put_pixel(x, 0, RED);
+        img.put_pixel(x, 2, WHITE);
+    }
+    let mut out = Vec::new();
+    preview::write_blocks(&mut out, &img, 20).unwrap();
+    let out = String::from_utf8(out).unwrap();
+    let lines: Vec<&str> = out.lines().collect();
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].starts_with("\x1b[38;2;255;0;0m\x1b[48;2;0;0;0m\u{2580}"));
+    assert!(lines[1].starts_with("\x1b[38;2;255;255;255m\x1b[48;2;255;255;255m\u{2580}"));
+}
+
+#[test]
+fn modes_parse_and_auto_follows_the_terminal() {
+    for mode in ["auto", "kitty", "sixel", "blocks"] {
+        assert_eq!(mode.parse::<PreviewMode>().unwrap().to_string(), mode);
+    }
+    assert!("iterm".parse::<PreviewMode>().is_err());
+
+    // The only test here that touches the environment.
+    let cases = [
+        (Some("1"), "xterm-256color", "", PreviewMode::Kitty),
+        (None, "xterm-kitty", "", PreviewMode::Kitty),
+        (None, "xterm-256color", "WezTerm", PreviewMode::Kitty),
+        (None, "xterm-256color", "ghostty", PreviewMode::Kitty),
+        (None, "foot", "", PreviewMode::Sixel),
+        (None, "mlterm", "", PreviewMode::Sixel),
+        (None, "xterm-sixel", "", PreviewMode::Sixel),
+        (
+            None,
+            "xterm-256color",
+            "Apple_Terminal",
+            PreviewMode::Blocks,
+        ),
+        (None, "", "", PreviewMode::Blocks),
+    ];
+    for (kitty_window, term, program, want) in cases {
+        match kitty_window {
+            Some(id) => env::set_var("KITTY_WINDOW_ID", id),
+            None => env::remove_var("KITTY_WINDOW_ID"),
+        }
+        env::set_var("TERM", term);
+        env::set_var("TERM_PROGRAM", program);
+        assert_eq!(
+            PreviewMode::Auto.resolve(),
+            want,
+            "TERM={term} TERM_PROGRAM={program}"
+        );
+        // Explicit modes are kept as they are.
+        assert_eq!(PreviewMode::Blocks.resolve(), PreviewMode::Blocks);
+    }
+}
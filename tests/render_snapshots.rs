@@ -0,0 +1,663 @@
+//! Golden-image tests for the screen renderers.
+//!
+//! Each test renders a fixed fixture and compares it with the reference PNG in
+//! `tests/snapshots/`. On mismatch the actual frame and a diff image are
+//! written to `target/snapshot-diffs/`. Run with `BLESS_SNAPSHOTS=1` to
+//! (re)write the references after an intentional layout change.
+
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+use chrono::{Local, NaiveDateTime, TimeZone};
+use geekmagic_common::budget::{Allowance, Period};
+use geekmagic_common::disk_render::{render_disk, DiskInfo};
+use geekmagic_common::network_render::{render_network, InterfaceInfo, NetworkInfo};
+use geekmagic_common::render::{
+    render_alert, render_bars, render_budget, render_status, RenderOptions,
+};
+use geekmagic_common::sensors_render::{render_sensors, Sensor, SensorInfo, SensorKind};
+use geekmagic_common::stats::{ActiveData, ExtraWindow, PaceInfo, UsageStatus, UsageWindow};
+use geekmagic_common::system_render::{render_system, SystemInfo};
+use image::{Rgba, RgbaImage};
+
+/// Largest per-channel difference treated as equal.
+const CHANNEL_TOLERANCE: u8 = 16;
+/// Fraction of pixels allowed to exceed the channel tolerance.
+const MAX_DIFF_FRACTION: f64 = 0.001;
+
+fn snapshot_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/snapshots")
+}
+
+fn diff_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("target/snapshot-diffs")
+}
+
+fn assert_snapshot(name: &str, actual: &RgbaImage) {
+    let reference_path = snapshot_dir().join(format!("{name}.png"));
+
+    if env::var_os("BLESS_SNAPSHOTS").is_some() {
+        fs::create_dir_all(snapshot_dir()).unwrap();
+        actual.save(&reference_path).unwrap();
+        return;
+    }
+
+    let reference = match image::open(&reference_path) {
+        Ok(img) => img.into_rgba8(),
+        Err(e) => panic!(
+            "missing reference {}: {e}; run with BLESS_SNAPSHOTS=1 to create it",
+            reference_path.display()
+        ),
+    };
+    assert_eq!(
+        reference.dimensions(),
+        actual.dimensions(),
+        "{name}: size differs from reference"
+    );
+
+    let mut diff = RgbaImage::new(actual.width(), actual.height());
+    let mut differing = 0u32;
+    for (x, y, px) in actual.enumerate_pixels() {
+        let expected = reference.get_pixel(x, y);
+        let delta = (0..3).map(|c| px[c].abs_diff(expected[c])).max().unwrap();
+        let out = if delta > CHANNEL_TOLERANCE {
+            differing += 1;
+            Rgba([255, 0, 255, 255])
+        } else {
+            // Dimmed reference so the highlighted pixels stand out.
+            Rgba([expected[0] / 3, expected[1] / 3, expected[2] / 3, 255])
+        };
+        diff.put_pixel(x, y, out);
+    }
+
+    let total = actual.width() * actual.height();
+    let fraction = differing as f64 / total as f64;
+    if fraction > MAX_DIFF_FRACTION {
+        fs::create_dir_all(diff_dir()).unwrap();
+        let actual_path = diff_dir().join(format!("{name}.actual.png"));
+        let diff_path = diff_dir().join(format!("{name}.diff.png"));
+        actual.save(&actual_path).unwrap();
+        diff.save(&diff_path).unwrap();
+        panic!(
+            "{name}: {differing} of {total} pixels differ from {}\n  actual: {}\n  diff:   {}\n\
+             run with BLESS_SNAPSHOTS=1 if the change is intended",
+            reference_path.display(),
+            actual_path.display(),
+            diff_path.display()
+        );
+    }
+}
+
+fn window(utilization: f64, resets_in_minutes: f64, usage_level: &str) -> UsageWindow {
+    UsageWindow {
+        utilization,
+        resets_in_minutes: Some(resets_in_minutes),
+        usage_level: usage_level.to_string(),
+        pace: None,
+        allowance: None,
+    }
+}
+
+fn with_pace(mut w: UsageWindow, expected: f64, eta_minutes: Option<f64>) -> UsageWindow {
+    w.pace = Some(PaceInfo {
+        delta_percent: w.utilization - expected,
+        expected_percent: expected,
+        will_last_to_reset: eta_minutes.is_none(),
+        eta_minutes,
+    });
+    w
+}
+
+/// The only stats page for `data`.
+fn bars(data: &ActiveData, opts: &RenderOptions) -> RgbaImage {
+    let mut pages = render_bars(data, opts).unwrap();
+    assert_eq!(pages.len(), 1, "expected a single stats page");
+    pages.remove(0)
+}
+
+/// Options with `now` fixed to a local wall-clock time.
+fn at(now: &str) -> RenderOptions {
+    let naive = NaiveDateTime::parse_from_str(now, "%Y-%m-%dT%H:%M:%S").unwrap();
+    RenderOptions {
+        now: Local.from_local_datetime(&naive).unwrap(),
+        ..RenderOptions::default()
+    }
+}
+
+fn dithered() -> RenderOptions {
+    RenderOptions {
+        dither: true,
+        ..RenderOptions::default()
+    }
+}
+
+fn system(cpu_percent: f64, memory_used_gb: f64, load1: f64) -> SystemInfo {
+    SystemInfo {
+        hostname: Some("build-01".to_string()),
+        cpus: 8,
+        cpu_percent,
+        memory_total_bytes: 32_000_000_000,
+        memory_used_bytes: (memory_used_gb * 1e9) as u64,
+        load: [load1, load1 * 0.8, load1 * 0.6],
+    }
+}
+
+/// An hour of readings (a minute apart) ramping up to a busy build.
+fn busy_history() -> Vec<SystemInfo> {
+    (0..60)
+        .map(|i| {
+            let t = i as f64 / 59.0;
+            let wave = (i as f64 / 4.0).sin() * 6.0;
+            system(
+                (8.0 + 80.0 * t * t + wave).clamp(0.0, 100.0),
+                12.0 + 14.0 * t,
+                0.5 + 7.5 * t,
+            )
+        })
+        .collect()
+}
+
+fn interface(name: &str, rx_rate: f64, tx_rate: f64) -> InterfaceInfo {
+    InterfaceInfo {
+        name: name.to_string(),
+        rx_bytes: 1_840_000_000_000,
+        tx_bytes: 312_000_000_000,
+        rx_rate,
+        tx_rate,
+    }
+}
+
+/// An hour of readings with a download burst on `eth0` and steady Wi-Fi.
+fn network_history() -> Vec<NetworkInfo> {
+    (0..60)
+        .map(|i| {
+            let burst = if (35..50).contains(&i) { 38e6 } else { 2e6 };
+            let wave = (i as f64 / 3.0).sin().abs();
+            NetworkInfo {
+                interfaces: vec![
+                    interface("eth0", burst + 1e6 * wave, 4e5 + 3e5 * wave),
+                    interface("wlan0", 6e5 + 2e5 * wave, 9e4),
+                ],
+            }
+        })
+        .collect()
+}
+
+fn sensor(
+    id: &str,
+    kind: SensorKind,
+    value: f64,
+    high: Option<f64>,
+    critical: Option<f64>,
+) -> Sensor {
+    Sensor {
+        id: id.to_string(),
+        label: id.rsplit('/').next().unwrap().to_string(),
+        kind,
+        value,
+        high,
+        critical,
+    }
+}
+
+/// A desktop under load: CPU package past its own high limit, cooler
+/// drives, and two fans.
+fn desktop_sensors() -> SensorInfo {
+    SensorInfo {
+        sensors: vec![
+            sensor(
+                "coretemp/Package id 0",
+                SensorKind::Temperature,
+                86.0,
+                Some(84.0),
+                Some(100.0),
+            ),
+            sensor(
+                "nvme/Composite",
+                SensorKind::Temperature,
+                47.9,
+                Some(81.8),
+                Some(84.8),
+            ),
+            sensor(
+                "thermal/acpitz",
+                SensorKind::Temperature,
+                27.8,
+                None,
+                Some(119.0),
+            ),
+            sensor("nct6775/CPU Fan", SensorKind::Fan, 1840.0, None, None),
+            sensor("nct6775/fan2", SensorKind::Fan, 0.0, None, None),
+        ],
+        cpu_mhz: Some((2380.0, 4700.0)),
+    }
+}
+
+fn disk(total_gb: u64, free_gb: u64) -> DiskInfo {
+    let total_bytes = total_gb * 1_000_000_000;
+    let free_bytes = free_gb * 1_000_000_000;
+    DiskInfo {
+        total_bytes,
+        free_bytes,
+        used_bytes: total_bytes - free_bytes,
+    }
+}
+
+#[test]
+fn bars_on_pace() {
+    let data = ActiveData {
+        five_hour: Some(with_pace(window(34.0, 190.0, "normal"), 36.7, None)),
+        seven_day: Some(with_pace(window(21.0, 6600.0, "normal"), 34.5, None)),
+        updated_at: None,
+        extra: BTreeMap::new(),
+    };
+    assert_snapshot("bars_on_pace", &bars(&data, &RenderOptions::default()));
+}
+
+#[test]
+fn bars_warn_and_deficit() {
+    let data = ActiveData {
+        five_hour: Some(with_pace(window(72.0, 150.0, "warn"), 50.0, Some(58.0))),
+        seven_day: Some(with_pace(window(64.0, 4320.0, "warn"), 57.1, Some(1900.0))),
+        updated_at: None,
+        extra: BTreeMap::new(),
+    };
+    assert_snapshot(
+        "bars_warn_and_deficit",
+        &bars(&data, &RenderOptions::default()),
+    );
+}
+
+#[test]
+fn bars_over_limit() {
+    // Utilization past 100% must not overflow the bar or the panel.
+    let data = ActiveData {
+        five_hour: Some(with_pace(window(112.0, 45.0, "over"), 85.0, Some(0.0))),
+        seven_day: Some(with_pace(window(100.0, 1440.0, "over"), 85.7, Some(0.0))),
+        updated_at: None,
+        extra: BTreeMap::new(),
+    };
+    assert_snapshot("bars_over_limit", &bars(&data, &RenderOptions::default()));
+}
+
+#[test]
+fn bars_single_window_without_pace() {
+    let data = ActiveData {
+        five_hour: Some(window(3.0, 298.0, "normal")),
+        seven_day: None,
+        updated_at: None,
+        extra: BTreeMap::new(),
+    };
+    assert_snapshot(
+        "bars_single_window",
+        &bars(&data, &RenderOptions::default()),
+    );
+}
+
+#[test]
+fn bars_no_data() {
+    let data = ActiveData {
+        five_hour: None,
+        seven_day: None,
+        updated_at: None,
+        extra: BTreeMap::new(),
+    };
+    assert_snapshot("bars_no_data", &bars(&data, &RenderOptions::default()));
+}
+
+#[test]
+fn bars_dithered() {
+    let data = ActiveData {
+        five_hour: Some(with_pace(window(88.0, 60.0, "danger"), 80.0, Some(40.0))),
+        seven_day: Some(with_pace(window(45.0, 5000.0, "normal"), 50.4, None)),
+        updated_at: None,
+        extra: BTreeMap::new(),
+    };
+    assert_snapshot("bars_dithered", &bars(&data, &dithered()));
+}
+
+fn extra(label: &str, w: UsageWindow) -> ExtraWindow {
+    ExtraWindow {
+        label: label.to_string(),
+        window_minutes: Some(7.0 * 24.0 * 60.0),
+        window: w,
+    }
+}
+
+#[test]
+fn bars_paginate_extra_windows() {
+    let data = ActiveData {
+        five_hour: Some(with_pace(window(34.0, 190.0, "normal"), 36.7, None)),
+        seven_day: Some(with_pace(window(21.0, 6600.0, "normal"), 34.5, None)),
+        updated_at: None,
+        extra: BTreeMap::from([
+            (
+                "seven_day_opus".to_string(),
+                extra(
+                    "Weekly Opus",
+                    with_pace(window(82.0, 6600.0, "danger"), 34.5, Some(2100.0)),
+                ),
+            ),
+            (
+                "seven_day_sonnet".to_string(),
+                extra("Weekly Sonnet", window(9.0, 6600.0, "normal")),
+            ),
+            (
+                "monthly".to_string(),
+                extra("Monthly", window(40.0, 20_000.0, "normal")),
+            ),
+        ]),
+    };
+    let pages = render_bars(&data, &RenderOptions::default()).unwrap();
+    assert_eq!(pages.len(), 3);
+    assert_snapshot("bars_paged_1", &pages[0]);
+    assert_snapshot("bars_paged_2", &pages[1]);
+    assert_snapshot("bars_paged_3", &pages[2]);
+}
+
+// Timestamps without an offset are read as local time, so these render the
+// same in every time zone.
+const LAST_GOOD: &str = "2026-10-18T14:32:00";
+const LAST_GOOD_NOW: &str = "2026-10-18T15:10:00";
+
+fn updated_at(ts: &str) -> ActiveData {
+    ActiveData {
+        five_hour: Some(with_pace(window(34.0, 190.0, "normal"), 36.7, None)),
+        seven_day: Some(with_pace(window(21.0, 6600.0, "normal"), 34.5, None)),
+        updated_at: Some(ts.to_string()),
+        extra: BTreeMap::new(),
+    }
+}
+
+#[test]
+fn bars_fresh_timestamp() {
+    let img = bars(&updated_at(LAST_GOOD), &at("2026-10-18T14:40:00"));
+    assert_snapshot("bars_fresh", &img);
+}
+
+#[test]
+fn bars_stale_shows_age_and_dims() {
+    let img = bars(&updated_at(LAST_GOOD), &at("2026-10-18T16:50:00"));
+    assert_snapshot("bars_stale", &img);
+}
+
+#[test]
+fn bars_stale_from_yesterday_shows_date() {
+    let img = bars(&updated_at(LAST_GOOD), &at("2026-10-19T09:00:00"));
+    assert_snapshot("bars_stale_yesterday", &img);
+}
+
+#[test]
+fn status_logged_out() {
+    let img = render_status(
+        &UsageStatus::LoggedOut,
+        None,
+        Some(LAST_GOOD),
+        &at(LAST_GOOD_NOW),
+    )
+    .unwrap();
+    assert_snapshot("status_logged_out", &img);
+}
+
+#[test]
+fn status_rate_limited() {
+    let img = render_status(
+        &UsageStatus::RateLimited,
+        None,
+        Some(LAST_GOOD),
+        &at(LAST_GOOD_NOW),
+    )
+    .unwrap();
+    assert_snapshot("status_rate_limited", &img);
+}
+
+#[test]
+fn status_network_error_with_message() {
+    let message = "error sending request for url (https://api.anthropic.com/api/oauth/usage): \
+                   operation timed out";
+    let img = render_status(
+        &UsageStatus::NetworkError,
+        Some(message),
+        None,
+        &at(LAST_GOOD_NOW),
+    )
+    .unwrap();
+    assert_snapshot("status_network_error", &img);
+}
+
+#[test]
+fn status_unknown() {
+    let status = UsageStatus::Unknown("maintenance".to_string());
+    let img = render_status(&status, None, Some(LAST_GOOD), &at(LAST_GOOD_NOW)).unwrap();
+    assert_snapshot("status_unknown", &img);
+}
+
+#[test]
+fn alert_page_danger() {
+    let data = ActiveData {
+        five_hour: Some(with_pace(window(91.0, 80.0, "danger"), 84.0, Some(25.0))),
+        seven_day: Some(window(48.0, 5000.0, "normal")),
+        updated_at: None,
+        extra: BTreeMap::new(),
+    };
+    assert_snapshot("alert_danger", &render_alert(&data).unwrap().unwrap());
+}
+
+#[test]
+fn alert_page_over_picks_the_fuller_window() {
+    let data = ActiveData {
+        five_hour: Some(window(97.0, 30.0, "danger")),
+        seven_day: Some(window(100.0, 1440.0, "over")),
+        updated_at: None,
+        extra: BTreeMap::new(),
+    };
+    assert_snapshot("alert_over", &render_alert(&data).unwrap().unwrap());
+}
+
+#[test]
+fn alert_page_absent_below_danger() {
+    let data = ActiveData {
+        five_hour: Some(window(72.0, 150.0, "warn")),
+        seven_day: None,
+        updated_at: None,
+        extra: BTreeMap::new(),
+    };
+    assert!(render_alert(&data).unwrap().is_none());
+}
+
+fn with_allowance(mut w: UsageWindow, period: Period, periods: u32) -> UsageWindow {
+    w.allowance = Some(Allowance {
+        percent: (100.0 - w.utilization).max(0.0) / f64::from(periods),
+        period,
+        periods,
+    });
+    w
+}
+
+#[test]
+fn budget_per_hour_and_work_day() {
+    let data = ActiveData {
+        five_hour: Some(with_allowance(
+            window(42.0, 100.0, "normal"),
+            Period::Hour,
+            2,
+        )),
+        seven_day: Some(with_allowance(
+            window(40.0, 5000.0, "normal"),
+            Period::WorkingDay,
+            4,
+        )),
+        updated_at: Some(LAST_GOOD.to_string()),
+        extra: BTreeMap::new(),
+    };
+    let pages = render_budget(&data, &at("2026-10-18T14:40:00")).unwrap();
+    assert_eq!(pages.len(), 1);
+    assert_snapshot("budget_work_days", &pages[0]);
+}
+
+#[test]
+fn budget_small_daily_allowance() {
+    let data = ActiveData {
+        five_hour: None,
+        seven_day: Some(with_allowance(
+            window(93.0, 8000.0, "danger"),
+            Period::Day,
+            6,
+        )),
+        updated_at: None,
+        extra: BTreeMap::new(),
+    };
+    assert_snapshot(
+        "budget_calendar_days",
+        &render_budget(&data, &RenderOptions::default()).unwrap()[0],
+    );
+}
+
+#[test]
+fn budget_exhausted() {
+    let data = ActiveData {
+        five_hour: Some(with_allowance(window(100.0, 45.0, "over"), Period::Hour, 1)),
+        seven_day: Some(with_allowance(window(71.0, 2000.0, "warn"), Period::Day, 2)),
+        updated_at: None,
+        extra: BTreeMap::new(),
+    };
+    assert_snapshot(
+        "budget_exhausted",
+        &render_budget(&data, &RenderOptions::default()).unwrap()[0],
+    );
+}
+
+#[test]
+fn budget_paginates_extra_windows() {
+    let data = ActiveData {
+        five_hour: Some(with_allowance(
+            window(42.0, 100.0, "normal"),
+            Period::Hour,
+            2,
+        )),
+        seven_day: Some(with_allowance(
+            window(40.0, 5000.0, "normal"),
+            Period::Day,
+            4,
+        )),
+        updated_at: None,
+        extra: BTreeMap::from([(
+            "seven_day_opus".to_string(),
+            extra(
+                "Weekly Opus",
+                with_allowance(window(70.0, 1400.0, "warn"), Period::Day, 1),
+            ),
+        )]),
+    };
+    let pages = render_budget(&data, &RenderOptions::default()).unwrap();
+    assert_eq!(pages.len(), 2);
+    assert_snapshot("budget_paged_1", &pages[0]);
+    assert_snapshot("budget_paged_2", &pages[1]);
+}
+
+#[test]
+fn disk_typical() {
+    assert_snapshot(
+        "disk_typical",
+        &render_disk(&disk(994, 412), false).unwrap(),
+    );
+}
+
+#[test]
+fn disk_nearly_full() {
+    assert_snapshot(
+        "disk_nearly_full",
+        &render_disk(&disk(500, 4), false).unwrap(),
+    );
+}
+
+#[test]
+fn disk_small_volume_dithered() {
+    assert_snapshot(
+        "disk_small_dithered",
+        &render_disk(&disk(64, 51), true).unwrap(),
+    );
+}
+
+#[test]
+fn system_busy_with_history() {
+    let history = busy_history();
+    assert_snapshot(
+        "system_busy",
+        &render_system(history.last().unwrap(), &history, false).unwrap(),
+    );
+}
+
+#[test]
+fn system_first_reading() {
+    // A one-shot run has a single reading, so the graphs are just a point.
+    let info = system(12.0, 9.5, 0.42);
+    assert_snapshot(
+        "system_first_reading",
+        &render_system(&info, std::slice::from_ref(&info), false).unwrap(),
+    );
+}
+
+#[test]
+fn system_short_history_dithered() {
+    let history: Vec<SystemInfo> = busy_history().into_iter().take(20).collect();
+    assert_snapshot(
+        "system_short_dithered",
+        &render_system(history.last().unwrap(), &history, true).unwrap(),
+    );
+}
+
+#[test]
+fn network_with_history() {
+    let history = network_history();
+    let pages = render_network(history.last().unwrap(), &history, false).unwrap();
+    assert_eq!(pages.len(), 1);
+    assert_snapshot("network_history", &pages[0]);
+}
+
+#[test]
+fn network_first_reading_paginates() {
+    // Three interfaces take two pages; a single reading has no graph yet.
+    let info = NetworkInfo {
+        interfaces: vec![
+            interface("en0", 12_400_000.0, 850_000.0),
+            interface("en1", 0.0, 0.0),
+            interface("utun3", 3_200.0, 410.0),
+        ],
+    };
+    let pages = render_network(&info, std::slice::from_ref(&info), false).unwrap();
+    assert_eq!(pages.len(), 2);
+    assert_snapshot("network_paged_1", &pages[0]);
+    assert_snapshot("network_paged_2", &pages[1]);
+}
+
+#[test]
+fn sensors_with_chip_limits() {
+    assert_snapshot(
+        "sensors_mixed",
+        &render_sensors(&desktop_sensors(), None, None, false).unwrap(),
+    );
+}
+
+#[test]
+fn sensors_configured_thresholds_override_chips() {
+    // 70/85 °C makes the package critical and the drive stays cool.
+    assert_snapshot(
+        "sensors_configured_dithered",
+        &render_sensors(&desktop_sensors(), Some(70.0), Some(85.0), true).unwrap(),
+    );
+}
+
+#[test]
+fn sensors_none_found() {
+    let info = SensorInfo {
+        sensors: Vec::new(),
+        cpu_mhz: None,
+    };
+    assert_snapshot(
+        "sensors_none",
+        &render_sensors(&info, None, None, false).unwrap(),
+    );
+}
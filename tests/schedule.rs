@@ -0,0 +1,342 @@
+//! Tests for working hours: counting working time and days across
+//! midnight, DST changes and time zones, and rescaling the pace with them.
+
+use chrono::{DateTime, Utc};
+use geekmagic_common::schedule::{self, WorkingHours};
+use geekmagic_common::stats::ActiveData;
+
+fn utc(text: &str) -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339(text).unwrap().into()
+}
+
+fn hours(hours: Option<&str>, days: Option<&[&str]>, zone: &str) -> WorkingHours {
+    let days: Option<Vec<String>> = days.map(|d| d.iter().map(|s| s.to_string()).collect());
+    WorkingHours::from_settings(hours, days.as_deref(), Some(zone))
+        .unwrap()
+        .unwrap()
+}
+
+const WEEKDAYS: &[&str] = &["mon", "tue", "wed", "thu", "fri"];
+
+#[test]
+fn working_minutes_table() {
+    // (name, hours, days, zone, from, to, minutes)
+    let cases = [
+        (
+            "a whole day",
+            Some("09:00-17:00"),
+            None,
+            "UTC",
+            "2026-01-05T00:00:00Z",
+            "2026-01-06T00:00:00Z",
+            480.0,
+        ),
+        (
+            "part of a shift",
+            Some("09:00-17:00"),
+            None,
+            "UTC",
+            "2026-01-05T12:00:00Z",
+            "2026-01-05T20:00:00Z",
+            300.0,
+        ),
+        (
+            "overnight, both ends of one day",
+            Some("22:00-02:00"),
+            None,
+            "UTC",
+            "2026-01-05T00:00:00Z",
+            "2026-01-06T00:00:00Z",
+            240.0,
+        ),
+        (
+            "overnight shift belongs to the day it starts",
+            Some("22:00-02:00"),
+            Some(&["mon"][..]),
+            "UTC",
+            "2026-01-05T00:00:00Z",
+            "2026-01-07T00:00:00Z",
+            240.0,
+        ),
+        (
+            "overnight from a Friday into the weekend",
+            Some("22:00-02:00"),
+            Some(WEEKDAYS),
+            "UTC",
+            "2026-01-10T00:00:00Z",
+            "2026-01-12T00:00:00Z",
+            120.0,
+        ),
+        (
+            "a whole week of working days",
+            Some("09:00-17:00"),
+            Some(WEEKDAYS),
+            "UTC",
+            "2026-01-05T00:00:00Z",
+            "2026-01-12T00:00:00Z",
+            5.0 * 480.0,
+        ),
+        (
+            "working days alone are whole days",
+            None,
+            Some(&["sat", "sun"][..]),
+            "UTC",
+            "2026-01-05T00:00:00Z",
+            "2026-01-12T00:00:00Z",
+            2.0 * 1440.0,
+        ),
+        (
+            "spring forward: a 23 hour day",
+            None,
+            Some(&["sun"][..]),
+            "Europe/Berlin",
+            "2026-03-28T23:00:00Z",
+            "2026-03-29T22:00:00Z",
+            1380.0,
+        ),
+        (
+            "fall back: a 25 hour day",
+            None,
+            Some(&["sun"][..]),
+            "Europe/Berlin",
+            "2026-10-24T22:00:00Z",
+            "2026-10-25T23:00:00Z",
+            1500.0,
+        ),
+        (
+            "a shift over the skipped hour",
+            Some("01:00-04:00"),
+            None,
+            "Europe/Berlin",
+            "2026-03-28T23:00:00Z",
+            "2026-03-29T22:00:00Z",
+            120.0,
+        ),
+        (
+            "a shift starting in the skipped hour",
+            Some("02:30-05:00"),
+            None,
+            "Europe/Berlin",
+            "2026-03-28T23:00:00Z",
+            "2026-03-29T22:00:00Z",
+            90.0,
+        ),
+        (
+            "a shift over the repeated hour",
+            Some("01:00-04:00"),
+            None,
+            "Europe/Berlin",
+            "2026-10-24T22:00:00Z",
+            "2026-10-25T23:00:00Z",
+            240.0,
+        ),
+        (
+            "named zone west of UTC",
+            Some("09:00-17:00"),
+            None,
+            "America/New_York",
+            "2026-01-05T00:00:00Z",
+            "2026-01-05T18:00:00Z",
+            240.0,
+        ),
+        (
+            "named zone east of UTC",
+            Some("09:00-17:00"),
+            None,
+            "Asia/Tokyo",
+            "2026-01-05T00:00:00Z",
+            "2026-01-05T04:00:00Z",
+            240.0,
+        ),
+        (
+            "empty range",
+            Some("09:00-17:00"),
+            None,
+            "UTC",
+            "2026-01-05T12:00:00Z",
+            "2026-01-05T12:00:00Z",
+            0.0,
+        ),
+    ];
+    for (name, h, days, zone, from, to, expected) in cases {
+        let got = hours(h, days, zone).working_minutes(utc(from), utc(to));
+        assert_eq!(got, expected, "{name}");
+    }
+}
+
+#[test]
+fn working_days_table() {
+    // (name, hours, days, zone, from, to, days)
+    let cases = [
+        (
+            "today counts while work is ahead",
+            Some("09:00-17:00"),
+            Some(WEEKDAYS),
+            "UTC",
+            "2026-01-09T10:00:00Z",
+            "2026-01-13T08:00:00Z",
+            2,
+        ),
+        (
+            "today is over after hours",
+            Some("09:00-17:00"),
+            Some(WEEKDAYS),
+            "UTC",
+            "2026-01-09T18:00:00Z",
+            "2026-01-12T12:00:00Z",
+            1,
+        ),
+        (
+            "the morning after an overnight shift",
+            Some("22:00-02:00"),
+            Some(&["fri"][..]),
+            "UTC",
+            "2026-01-10T00:00:00Z",
+            "2026-01-10T12:00:00Z",
+            1,
+        ),
+        (
+            "days follow the named zone",
+            Some("09:00-17:00"),
+            Some(&["mon"][..]),
+            "Asia/Tokyo",
+            // Sunday 22:00 UTC is Monday 07:00 in Tokyo.
+            "2026-01-04T22:00:00Z",
+            "2026-01-05T12:00:00Z",
+            1,
+        ),
+        (
+            "a DST day is one day",
+            None,
+            Some(&["sun"][..]),
+            "Europe/Berlin",
+            "2026-03-28T23:00:00Z",
+            "2026-03-30T22:00:00Z",
+            1,
+        ),
+        (
+            "a week of working days",
+            None,
+            Some(WEEKDAYS),
+            "UTC",
+            "2026-01-05T00:00:00Z",
+            "2026-01-19T00:00:00Z",
+            10,
+        ),
+    ];
+    for (name, h, days, zone, from, to, expected) in cases {
+        let got = hours(h, days, zone).working_days(utc(from), utc(to));
+        assert_eq!(got, expected, "{name}");
+    }
+}
+
+#[test]
+fn bad_settings_are_rejected() {
+    let cases = [
+        (Some("9-17"), None, None, "invalid working_hours `9-17`"),
+        (Some("09:00-25:00"), None, None, "invalid working_hours"),
+        (Some("09:00"), None, None, "invalid working_hours"),
+        (
+            None,
+            Some("funday"),
+            None,
+            "invalid working_days entry `funday`",
+        ),
+        (
+            None,
+            Some("mon"),
+            Some("Mars/Base"),
+            "unknown timezone `Mars/Base`",
+        ),
+    ];
+    for (h, day, zone, prefix) in cases {
+        let days = day.map(|d| vec![d.to_string()]);
+        let err = WorkingHours::from_settings(h, days.as_deref(), zone).unwrap_err();
+        assert!(err.to_string().starts_with(prefix), "{err}");
+    }
+    assert_eq!(WorkingHours::from_settings(None, None, None).unwrap(), None);
+}
+
+fn session(utilization: f64, resets_in: f64, pace: serde_json::Value) -> ActiveData {
+    serde_json::from_value(serde_json::json!({
+        "five_hour": {
+            "utilization": utilization,
+            "resets_in_minutes": resets_in,
+            "usage_level": "normal",
+            "pace": pace,
+        }
+    }))
+    .unwrap()
+}
+
+#[test]
+fn apply_rescales_pace_to_working_time() {
+    let office = hours(Some("09:00-17:00"), None, "UTC");
+    // A session from 12:00 to 17:00 is all working time: unchanged.
+    let now = utc("2026-01-05T14:30:00Z");
+    let mut data = session(
+        40.0,
+        150.0,
+        serde_json::json!({
+            "delta_percent": -10.0,
+            "expected_percent": 50.0,
+            "will_last_to_reset": true,
+            "eta_minutes": null,
+        }),
+    );
+    schedule::apply(&office, &mut data, now);
+    let pace = data.five_hour.unwrap().pace.unwrap();
+    assert_eq!(pace.expected_percent, 50.0);
+    assert_eq!(pace.delta_percent, -10.0);
+
+    // From 16:00 to 21:00 only the first hour is working time.
+    let now = utc("2026-01-05T16:30:00Z");
+    let mut data = session(
+        40.0,
+        270.0,
+        serde_json::json!({
+            "delta_percent": 30.0,
+            "expected_percent": 10.0,
+            "will_last_to_reset": true,
+            "eta_minutes": null,
+        }),
+    );
+    schedule::apply(&office, &mut data, now);
+    let pace = data.five_hour.unwrap().pace.unwrap();
+    assert_eq!(pace.expected_percent, 50.0);
+    assert_eq!(pace.delta_percent, -10.0);
+    // The run-out estimate isn't touched.
+    assert!(pace.will_last_to_reset);
+}
+
+#[test]
+fn apply_fills_in_pace_once_working_time_passes() {
+    let office = hours(Some("09:00-17:00"), None, "UTC");
+    // Eight minutes into a session from 16:00: under 3% of the window, but
+    // 8 of its 60 working minutes.
+    let now = utc("2026-01-05T16:08:00Z");
+    let mut data = session(20.0, 292.0, serde_json::Value::Null);
+    schedule::apply(&office, &mut data, now);
+    let pace = data.five_hour.unwrap().pace.expect("pace filled in");
+    assert!(
+        (pace.expected_percent - 800.0 / 60.0).abs() < 1e-9,
+        "{pace:?}"
+    );
+    assert!((pace.delta_percent - (20.0 - 800.0 / 60.0)).abs() < 1e-9);
+    // 80 points left at 2.5 a minute.
+    assert!(!pace.will_last_to_reset);
+    assert!((pace.eta_minutes.unwrap() - 32.0).abs() < 1e-9, "{pace:?}");
+
+    // Before work starts there is no working time to go by.
+    let now = utc("2026-01-05T08:01:00Z");
+    let mut data = session(1.0, 299.0, serde_json::Value::Null);
+    schedule::apply(&office, &mut data, now);
+    assert!(data.five_hour.unwrap().pace.is_none());
+
+    // Nor is there a pace for a window without working time.
+    let weekdays = hours(Some("09:00-17:00"), Some(WEEKDAYS), "UTC");
+    let sunday = utc("2026-01-11T12:00:00Z");
+    let mut data = session(20.0, 295.0, serde_json::Value::Null);
+    schedule::apply(&weekdays, &mut data, sunday);
+    assert!(data.five_hour.unwrap().pace.is_none());
+}
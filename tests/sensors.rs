@@ -0,0 +1,78 @@
+//! Tests for reading sensors from sysfs.
+//!
+//! `tests/fixtures/sysfs/` is a trimmed copy of the files a desktop exposes
+//! under `/sys`: a CPU package and core, an NVMe drive that shows up both as
+//! an hwmon chip and a thermal zone, an ACPI thermal zone and two fans.
+
+use std::path::PathBuf;
+
+use geekmagic_common::sensors_render::{
+    format_clock, read_sensor_info, SensorInfo, SensorKind, MAX_SENSORS,
+};
+
+fn read(wanted: Option<&[String]>) -> SensorInfo {
+    let sys = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/sysfs");
+    read_sensor_info(&sys, wanted)
+}
+
+fn ids(info: &SensorInfo) -> Vec<&str> {
+    info.sensors.iter().map(|s| s.id.as_str()).collect()
+}
+
+#[test]
+fn hottest_first_then_fans() {
+    let info = read(None);
+    assert_eq!(
+        ids(&info),
+        [
+            "coretemp/Package id 0",
+            "coretemp/Core 0",
+            "nvme/Composite",
+            "thermal/acpitz",
+            "nct6775/CPU Fan",
+        ]
+    );
+    assert_eq!(info.sensors.len(), MAX_SENSORS);
+}
+
+#[test]
+fn units_and_limits() {
+    let info = read(None);
+    let package = &info.sensors[0];
+    assert_eq!(package.kind, SensorKind::Temperature);
+    assert_eq!(package.label, "Package id 0");
+    assert_eq!(package.value, 71.0);
+    assert_eq!(package.high, Some(84.0));
+    assert_eq!(package.critical, Some(100.0));
+
+    // Thermal zones take their limits from the trip points.
+    let zone = &info.sensors[3];
+    assert_eq!(zone.value, 27.8);
+    assert_eq!(zone.high, Some(95.0));
+    assert_eq!(zone.critical, Some(119.0));
+
+    let fan = &info.sensors[4];
+    assert_eq!(fan.kind, SensorKind::Fan);
+    assert_eq!(fan.value, 1840.0);
+
+    assert_eq!(info.cpu_mhz, Some((2100.0, 4700.0)));
+}
+
+#[test]
+fn configured_sensors_in_order() {
+    // Unlabelled sensors go by their sysfs name; matching ignores case and
+    // skips patterns that match nothing.
+    let wanted = ["FAN2", "missing", "nvme", "core"].map(String::from);
+    let info = read(Some(&wanted));
+    assert_eq!(
+        ids(&info),
+        ["nct6775/fan2", "nvme/Composite", "coretemp/Package id 0"]
+    );
+}
+
+#[test]
+fn clock_uses_one_unit() {
+    assert_eq!(format_clock(800.0, 4200.0), "0.8 / 4.2 GHz");
+    assert_eq!(format_clock(3600.0, 4200.0), "3.6 / 4.2 GHz");
+    assert_eq!(format_clock(600.0, 900.0), "600 / 900 MHz");
+}
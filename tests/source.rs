@@ -0,0 +1,162 @@
+//! Tests for parsing `source` specs, the sources that don't need the
+//! in-process collector, and how failed fetches are classified.
+
+use std::fs;
+use std::io::{Read, Write};
+use std::net::TcpListener;
+use std::path::Path;
+use std::thread;
+
+use geekmagic_common::source::{self, DEFAULT_SOURCE};
+use geekmagic_common::stats::{self, Stats, UsageStatus};
+
+#[test]
+fn specs_pick_the_source() {
+    let cases = [
+        (DEFAULT_SOURCE, "claude-code-stats"),
+        ("  claude-code-stats  ", "claude-code-stats"),
+        ("stdin", "stdin"),
+        ("file:/tmp/usage.json", "file /tmp/usage.json"),
+        ("file:relative/usage.json", "file relative/usage.json"),
+        ("command:cat usage.json", "command `cat usage.json`"),
+        ("http://127.0.0.1:8080/usage", "http://127.0.0.1:8080/usage"),
+        ("https://example.com/usage", "https://example.com/usage"),
+    ];
+    for (spec, described) in cases {
+        let source = source::parse(spec).unwrap_or_else(|e| panic!("{spec}: {e}"));
+        assert_eq!(source.describe(), described, "{spec}");
+    }
+}
+
+#[test]
+fn bad_specs_are_rejected() {
+    let cases = [
+        ("", "unknown source ``"),
+        ("claude", "unknown source `claude`"),
+        ("cmd:cat usage.json", "unknown source `cmd:cat usage.json`"),
+        ("ftp://example.com/usage", "unknown source `ftp://"),
+        ("-", "unknown source `-`"),
+        ("file:", "source `file:` needs a path"),
+        ("command:  ", "source `command:` needs a command"),
+    ];
+    for (spec, prefix) in cases {
+        let err = match source::parse(spec) {
+            Ok(source) => panic!("{spec}: accepted as {}", source.describe()),
+            Err(e) => e.to_string(),
+        };
+        assert!(err.starts_with(prefix), "{spec}: {err}");
+    }
+}
+
+#[test]
+fn unknown_source_lists_the_valid_ones() {
+    let err = source::parse("clipboard").err().unwrap().to_string();
+    for expected in [
+        "claude-code-stats",
+        "stdin",
+        "file:PATH",
+        "command:CMD",
+        "http(s)",
+    ] {
+        assert!(err.contains(expected), "{err}");
+    }
+}
+
+#[test]
+fn file_and_command_sources_fetch() {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("source-fetch");
+    fs::create_dir_all(&dir).unwrap();
+    let file = dir.join("usage.json");
+    fs::write(&file, r#"{"status":"logged_out"}"#).unwrap();
+
+    let source = source::parse(&format!("file:{}", file.display())).unwrap();
+    assert_eq!(source.fetch_json().unwrap(), r#"{"status":"logged_out"}"#);
+    let source = source::parse(&format!("command:cat '{}'", file.display())).unwrap();
+    assert_eq!(source.fetch_json().unwrap(), r#"{"status":"logged_out"}"#);
+
+    let missing = source::parse(&format!("file:{}", dir.join("missing.json").display())).unwrap();
+    assert!(missing.fetch_json().is_err());
+    let failing = source::parse("command:echo oops >&2; exit 3").unwrap();
+    let err = failing.fetch_json().unwrap_err().to_string();
+    assert!(
+        err.contains("exit status: 3") && err.ends_with("oops"),
+        "{err}"
+    );
+}
+
+fn fetch_status(spec: &str) -> (UsageStatus, String) {
+    let source = source::parse(spec).unwrap();
+    let fetched = stats::fetch_stats(source.as_ref()).unwrap_or_else(|e| Stats::from_error(&e));
+    match fetched {
+        Stats::Inactive { status, message } => (status, message.unwrap_or_default()),
+        Stats::Active(_) => panic!("{spec}: fetched active data"),
+    }
+}
+
+/// Answers one request on a local port with `status`.
+fn serve_once(status: &str) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let response = format!("HTTP/1.1 {status}\r\ncontent-length: 0\r\nconnection: close\r\n\r\n");
+    thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut request = [0; 1024];
+        let _ = stream.read(&mut request);
+        stream.write_all(response.as_bytes()).unwrap();
+    });
+    format!("http://{addr}/usage")
+}
+
+#[test]
+fn failed_fetches_are_classified_by_kind() {
+    let (status, message) = fetch_status(&serve_once("401 Unauthorized"));
+    assert_eq!(status, UsageStatus::LoggedOut, "{message}");
+    let (status, message) = fetch_status(&serve_once("429 Too Many Requests"));
+    assert_eq!(status, UsageStatus::RateLimited, "{message}");
+    let (status, message) = fetch_status(&serve_once("500 Internal Server Error"));
+    assert_eq!(status, UsageStatus::Unknown("error".into()), "{message}");
+
+    // Nothing listens on a port we just released.
+    let addr = TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap();
+    let (status, message) = fetch_status(&format!("http://{addr}/usage"));
+    assert_eq!(status, UsageStatus::NetworkError, "{message}");
+
+    // A failure that merely mentions a network word isn't a network error.
+    let (status, message) = fetch_status("command:echo 'cannot connect: 401 times' >&2; exit 1");
+    assert_eq!(status, UsageStatus::Unknown("error".into()));
+    assert!(message.ends_with("cannot connect: 401 times"), "{message}");
+}
+
+#[test]
+fn error_payloads_are_refined_by_http_status() {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("source-status");
+    fs::create_dir_all(&dir).unwrap();
+    let cases = [
+        (
+            "usage API failed (429 Too Many Requests): slow down",
+            UsageStatus::RateLimited,
+        ),
+        (
+            "API request failed with status 403 Forbidden: {}",
+            UsageStatus::LoggedOut,
+        ),
+        (
+            "could not connect to the keychain",
+            UsageStatus::Unknown("error".into()),
+        ),
+    ];
+    for (i, (message, expected)) in cases.into_iter().enumerate() {
+        let file = dir.join(format!("error-{i}.json"));
+        let payload = serde_json::json!({
+            "status": "error",
+            "title": "Claude usage error",
+            "message": message,
+        });
+        fs::write(&file, payload.to_string()).unwrap();
+        let (status, _) = fetch_status(&format!("file:{}", file.display()));
+        assert_eq!(status, expected, "{message}");
+    }
+}
@@ -0,0 +1,151 @@
+//! Tests for the daemon's status API: the `/status` document, the summary
+//! `geekmagic-stats status` prints from it, and serving both.
+
+use std::sync::{Arc, Mutex};
+
+use chrono::{DateTime, Duration, Utc};
+use geekmagic_common::status;
+use geekmagic_common::telemetry::{FetchRecord, Telemetry};
+use image::{Rgba, RgbaImage};
+
+fn utc(text: &str) -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339(text).unwrap().into()
+}
+
+fn telemetry() -> Telemetry {
+    let mut t = Telemetry {
+        started_at: Some(Utc::now() - Duration::minutes(134)),
+        target: "10.0.1.102".to_string(),
+        interval_seconds: 300,
+        next_run: Some(Utc::now() + Duration::seconds(192)),
+        last_fetch: Some(FetchRecord {
+            at: utc("2026-01-05T14:30:10Z"),
+            source: "claude-code-stats".to_string(),
+            outcome: Ok("active".to_string()),
+            message: None,
+        }),
+        device_up: Some(true),
+        upload_seconds: Some(0.27),
+        upload_bytes: Some(20412),
+        ..Telemetry::default()
+    };
+    for page in ["stats", "disk"] {
+        t.uploads
+            .insert(page.to_string(), utc("2026-01-05T14:30:11Z"));
+        t.frames.insert(
+            page.to_string(),
+            RgbaImage::from_pixel(240, 240, Rgba([0, 0, 0, 255])),
+        );
+    }
+    t.record_error("device timed out".to_string());
+    t.errors[0].at = utc("2026-01-05T14:25:00Z");
+    t.record_success();
+    t
+}
+
+#[test]
+fn status_document_describes_the_daemon() {
+    let doc = status::to_json(&telemetry());
+    assert_eq!(doc["pid"], std::process::id());
+    assert_eq!(doc["target"], "10.0.1.102");
+    assert_eq!(doc["interval_seconds"], 300);
+    assert_eq!(doc["last_fetch"]["at"], "2026-01-05T14:30:10+00:00");
+    assert_eq!(doc["last_fetch"]["source"], "claude-code-stats");
+    assert_eq!(doc["last_fetch"]["status"], "active");
+    assert!(doc["last_fetch"]["error"].is_null());
+    assert_eq!(doc["device_up"], true);
+    assert_eq!(doc["last_upload"]["bytes"], 20412);
+    assert_eq!(doc["uploads"]["disk"]["at"], "2026-01-05T14:30:11+00:00");
+    assert_eq!(doc["errors"][0]["message"], "device timed out");
+    assert_eq!(doc["errors_total"], 1);
+    assert_eq!(doc["updates_total"], 1);
+    assert_eq!(
+        doc["frames"],
+        serde_json::json!(["/frames/disk.png", "/frames/stats.png"])
+    );
+}
+
+#[test]
+fn failed_fetches_and_fresh_daemons_are_summarized() {
+    let mut t = telemetry();
+    t.last_fetch = Some(FetchRecord {
+        at: utc("2026-01-05T14:30:10Z"),
+        source: "file /tmp/usage.json".to_string(),
+        outcome: Err("failed to read /tmp/usage.json".to_string()),
+        message: None,
+    });
+    t.device_up = Some(false);
+    let doc = status::to_json(&t);
+    assert!(doc["last_fetch"]["status"].is_null());
+    assert_eq!(doc["last_fetch"]["error"], "failed to read /tmp/usage.json");
+    let summary = status::summary(&doc, "127.0.0.1:9185");
+    assert!(
+        summary.contains("from file /tmp/usage.json: failed: failed to read /tmp/usage.json"),
+        "{summary}"
+    );
+    assert!(
+        summary.contains("Device: unreachable on the last push"),
+        "{summary}"
+    );
+
+    let summary = status::summary(&status::to_json(&Telemetry::default()), "127.0.0.1:9185");
+    assert!(summary.contains("Last fetch: none yet"), "{summary}");
+    assert!(summary.contains("Device: not contacted yet"), "{summary}");
+    for section in ["Last uploads:", "Recent errors", "Frames:"] {
+        assert!(!summary.contains(section), "{summary}");
+    }
+}
+
+#[test]
+fn summary_lists_the_daemon_state() {
+    std::env::set_var("TZ", "UTC");
+    let doc = status::to_json(&telemetry());
+    let summary = status::summary(&doc, "127.0.0.1:9185");
+    let lines: Vec<&str> = summary.lines().collect();
+    assert!(
+        lines[0].starts_with(&format!("Daemon: pid {}, up 2h 1", std::process::id())),
+        "{summary}"
+    );
+    assert!(
+        lines[0].ends_with("pushing every 300s to 10.0.1.102"),
+        "{summary}"
+    );
+    assert!(lines[1].starts_with("Next run: "), "{summary}");
+    assert!(lines[1].contains("(in 3m "), "{summary}");
+    assert_eq!(
+        lines[2..],
+        [
+            "Last fetch: 14:30:10 from claude-code-stats: active",
+            "Device: reachable (last push 0.27s, 20412 bytes)",
+            "Last uploads:",
+            "  disk   14:30:11",
+            "  stats  14:30:11",
+            "Recent errors (1 total):",
+            "  14:25:00 device timed out",
+            "Frames:",
+            "  http://127.0.0.1:9185/frames/disk.png",
+            "  http://127.0.0.1:9185/frames/stats.png",
+        ]
+    );
+}
+
+#[test]
+fn serves_status_and_frames() {
+    let shared = Arc::new(Mutex::new(telemetry()));
+    let bound = status::serve("127.0.0.1:0", shared).unwrap().to_string();
+    let doc = status::query(&bound).unwrap();
+    assert_eq!(doc["target"], "10.0.1.102");
+
+    let frame = reqwest::blocking::get(format!("http://{bound}/frames/stats.png")).unwrap();
+    assert_eq!(frame.status(), 200);
+    let png = image::load_from_memory(&frame.bytes().unwrap()).unwrap();
+    assert_eq!((png.width(), png.height()), (240, 240));
+    let missing = reqwest::blocking::get(format!("http://{bound}/frames/budget.png")).unwrap();
+    assert_eq!(missing.status(), 404);
+
+    let err = status::query("127.0.0.1:1").unwrap_err().to_string();
+    assert!(
+        err.starts_with("no daemon answering on 127.0.0.1:1"),
+        "{err}"
+    );
+}
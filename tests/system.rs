@@ -0,0 +1,90 @@
+//! Tests for the system page's `/proc` parsers.
+//!
+//! `tests/fixtures/proc/` holds captured files from a four-core desktop,
+//! plus `meminfo-3.10` from a kernel without `MemAvailable`.
+
+use std::fs;
+use std::path::PathBuf;
+
+use geekmagic_common::system_render::{parse_loadavg, parse_meminfo, parse_proc_stat, CpuTimes};
+
+fn fixture(name: &str) -> String {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/proc")
+        .join(name);
+    fs::read_to_string(path).unwrap()
+}
+
+#[test]
+fn proc_stat_sums_the_aggregate_line() {
+    let (times, cpus) = parse_proc_stat(&fixture("stat")).unwrap();
+    assert_eq!(cpus, 4);
+    // user through steal; idle and iowait are the idle share, guest is
+    // already in user.
+    assert_eq!(
+        times,
+        CpuTimes {
+            busy: 13_532_763,
+            total: 60_377_929,
+        }
+    );
+}
+
+#[test]
+fn cpu_percent_is_the_busy_share_between_readings() {
+    let earlier = CpuTimes {
+        busy: 1_000,
+        total: 4_000,
+    };
+    let later = CpuTimes {
+        busy: 1_300,
+        total: 4_400,
+    };
+    assert_eq!(later.percent_since(earlier), 75.0);
+    assert_eq!(earlier.percent_since(earlier), 0.0);
+    // Counters that went backwards (e.g. a restored VM) read as idle.
+    assert_eq!(earlier.percent_since(later), 0.0);
+}
+
+#[test]
+fn proc_stat_without_a_cpu_line_is_an_error() {
+    let err = parse_proc_stat("intr 1 2 3\nctxt 4\n").unwrap_err();
+    assert_eq!(err.to_string(), "no cpu line in /proc/stat");
+    let err = parse_proc_stat("cpu  1 2 x 4\n").unwrap_err();
+    assert_eq!(err.to_string(), "malformed cpu line in /proc/stat");
+    // A single-CPU machine without per-CPU lines still has one.
+    assert_eq!(parse_proc_stat("cpu  1 0 1 2 0 0 0 0\n").unwrap().1, 1);
+}
+
+#[test]
+fn meminfo_uses_available_memory() {
+    let (total, used) = parse_meminfo(&fixture("meminfo")).unwrap();
+    assert_eq!(total, 32_597_812 * 1024);
+    assert_eq!(used, (32_597_812 - 20_354_292) * 1024);
+}
+
+#[test]
+fn meminfo_before_3_14_adds_up_free_memory() {
+    let (total, used) = parse_meminfo(&fixture("meminfo-3.10")).unwrap();
+    assert_eq!(total, 8_061_604 * 1024);
+    assert_eq!(used, (8_061_604 - 945_812 - 202_640 - 3_528_812) * 1024);
+    let err = parse_meminfo("MemFree: 1 kB\n").unwrap_err();
+    assert_eq!(err.to_string(), "no MemTotal in /proc/meminfo");
+}
+
+#[test]
+fn load_averages_from_proc_and_sysctl() {
+    assert_eq!(
+        parse_loadavg(&fixture("loadavg")).unwrap(),
+        [1.20, 1.05, 0.98]
+    );
+    // `sysctl -n vm.loadavg` on macOS.
+    assert_eq!(
+        parse_loadavg("{ 2.42 1.87 1.66 }\n").unwrap(),
+        [2.42, 1.87, 1.66]
+    );
+    for bad in ["", "1.0 0.5", "one two three"] {
+        let err = parse_loadavg(bad).unwrap_err().to_string();
+        assert!(err.starts_with("malformed load average"), "{bad}: {err}");
+    }
+}